@@ -0,0 +1,405 @@
+//! # smlang
+//!
+//! Command line tool which finds `statemachine!` invocations in Rust source files and lints,
+//! lists, renders or verifies them without building the crate they are part of.
+
+// The macro crate's modules are shared as-is, not everything in them is used by the tool.
+#[allow(dead_code)]
+#[path = "../../macros/src/diagramgen.rs"]
+mod diagramgen;
+#[allow(dead_code)]
+#[path = "../../macros/src/parser/mod.rs"]
+mod parser;
+mod source;
+#[allow(dead_code)]
+#[path = "../../macros/src/validation.rs"]
+mod validation;
+mod verify;
+
+use source::{Diagnostic, Machine};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use verify::{Property, Verdict};
+
+const USAGE: &str = "\
+Usage: smlang <COMMAND> [OPTIONS] [PATHS]...
+
+Finds `statemachine!` invocations in the Rust files in PATHS (default: the current directory).
+
+Commands:
+  check     Validate every state machine and report errors
+  list      List the states, events and transitions of every state machine
+  render    Render a diagram of every state machine
+  verify    Check properties of every state machine for all results of its guards
+
+Options:
+  -m, --machine <NAME>  Only use the state machines named NAME
+  -h, --help            Print this help
+
+Render options:
+  -f, --format <FORMAT>  Output format, `dot` or any format supported by graphviz (default: svg)
+  -o, --output <DIR>     Directory to write the diagrams to (default: the current directory)
+
+Verify options, each can be given multiple times:
+  --reachable <STATE>         STATE can be reached
+  --always-reachable <STATE>  STATE can be reached from every reachable state
+  --requires <STATE>:<VIA>    Every path to STATE passes through VIA
+
+A property is only verified for the state machines which have all of its states.
+";
+
+enum Subcommand {
+    Check,
+    List,
+    Render { format: String, output: PathBuf },
+    Verify { properties: Vec<Property> },
+}
+
+struct Options {
+    command: Subcommand,
+    machine: Option<String>,
+    paths: Vec<PathBuf>,
+}
+
+/// Parses the command line arguments, returns `None` if help is requested.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let command = args.next().ok_or("missing command")?;
+    if command == "-h" || command == "--help" {
+        return Ok(None);
+    }
+
+    let mut format = String::from("svg");
+    let mut output = PathBuf::from(".");
+    let mut properties = Vec::new();
+    let mut machine = None;
+    let mut paths = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-m" | "--machine" => {
+                machine = Some(args.next().ok_or("missing value for --machine")?);
+            }
+            "-f" | "--format" => format = args.next().ok_or("missing value for --format")?,
+            "-o" | "--output" => {
+                output = args.next().ok_or("missing value for --output")?.into();
+            }
+            "--reachable" => properties.push(Property::Reachable(
+                args.next().ok_or("missing value for --reachable")?,
+            )),
+            "--always-reachable" => properties.push(Property::AlwaysReachable(
+                args.next().ok_or("missing value for --always-reachable")?,
+            )),
+            "--requires" => {
+                let value = args.next().ok_or("missing value for --requires")?;
+                let (state, via) = value
+                    .split_once(':')
+                    .ok_or("expected `<STATE>:<VIA>` for --requires")?;
+                properties.push(Property::Requires {
+                    state: state.to_string(),
+                    via: via.to_string(),
+                });
+            }
+            flag if flag.starts_with('-') => return Err(format!("unknown option `{}`", flag)),
+            path => paths.push(PathBuf::from(path)),
+        }
+    }
+
+    if paths.is_empty() {
+        paths.push(PathBuf::from("."));
+    }
+
+    let command = match command.as_str() {
+        "check" => Subcommand::Check,
+        "list" => Subcommand::List,
+        "render" => Subcommand::Render { format, output },
+        "verify" if properties.is_empty() => return Err("missing properties to verify".into()),
+        "verify" => Subcommand::Verify { properties },
+        command => return Err(format!("unknown command `{}`", command)),
+    };
+
+    Ok(Some(Options {
+        command,
+        machine,
+        paths,
+    }))
+}
+
+/// Loads every state machine found in `paths`. Files which cannot be read or parsed, invalid state
+/// machines and lint warnings are reported as diagnostics.
+fn load(paths: &[PathBuf]) -> Result<(Vec<Machine>, Vec<Diagnostic>), String> {
+    let files = source::collect_files(paths).map_err(|e| e.to_string())?;
+
+    let mut machines = Vec::new();
+    let mut diagnostics = Vec::new();
+    for path in files {
+        let source = fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        match source::find_machines(&path, &source) {
+            Ok(found) => machines.extend(found),
+            Err(error) => diagnostics.extend(Diagnostic::from_error(&path, 1, "error", &error)),
+        }
+    }
+
+    for machine in &machines {
+        if let Err(error) = &machine.parsed {
+            diagnostics.extend(Diagnostic::from_error(
+                &machine.path,
+                machine.line,
+                "error",
+                error,
+            ));
+        }
+        for warning in &machine.warnings {
+            diagnostics.extend(Diagnostic::from_error(
+                &machine.path,
+                machine.line,
+                "warning",
+                warning,
+            ));
+        }
+    }
+
+    Ok((machines, diagnostics))
+}
+
+fn list(machine: &Machine) {
+    let sm = match &machine.parsed {
+        Ok(sm) => sm,
+        Err(_) => return,
+    };
+
+    println!(
+        "{}:{}: {}",
+        machine.path.display(),
+        machine.line,
+        machine.display_name()
+    );
+
+    let mut states: Vec<_> = sm.states.keys().collect();
+    states.sort();
+    println!("  states:");
+    for state in states {
+        let marker = if sm.starting_state == state { "*" } else { " " };
+        match sm.state_data.data_types.get(state) {
+            Some(data_type) => println!("   {}{}({})", marker, state, quote::quote!(#data_type)),
+            None => println!("   {}{}", marker, state),
+        }
+    }
+
+    let mut events: Vec<_> = sm.events.keys().collect();
+    events.sort();
+    println!("  events:");
+    for event in events {
+        match sm.event_data.data_types.get(event) {
+            Some(data_type) => println!("    {}({})", event, quote::quote!(#data_type)),
+            None => println!("    {}", event),
+        }
+    }
+
+    println!("  transitions:");
+    for (event_mapping, transition) in sm.transitions() {
+        let guard = transition
+            .guard
+            .as_ref()
+            .map(|guard| format!(" [{}]", guard.as_written()))
+            .unwrap_or_default();
+        let action = transition
+            .action
+            .as_ref()
+            .map(|action| format!(" / {}", action))
+            .unwrap_or_default();
+        println!(
+            "    {} + {}{}{} = {}",
+            event_mapping.in_state, event_mapping.event, guard, action, transition.out_state
+        );
+    }
+}
+
+fn render(machine: &Machine, format: &str, output: &Path) -> Result<(), String> {
+    let sm = match &machine.parsed {
+        Ok(sm) => sm,
+        Err(_) => return Ok(()),
+    };
+
+    let diagram = diagramgen::generate_diagram(sm);
+    let file = output.join(format!(
+        "statemachine_{}.{}",
+        diagramgen::diagram_name(sm, &diagram),
+        format
+    ));
+
+    if format == "dot" {
+        fs::write(&file, diagram).map_err(|e| format!("{}: {}", file.display(), e))?;
+    } else {
+        let mut process = Command::new("dot")
+            .arg(format!("-T{}", format))
+            .arg("-o")
+            .arg(&file)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|_| "failed to execute 'dot'. Are you sure graphviz is installed?")?;
+
+        if let Some(mut stdin) = process.stdin.take() {
+            stdin
+                .write_all(diagram.as_bytes())
+                .map_err(|e| format!("failed to write to 'dot': {}", e))?;
+        }
+
+        let status = process.wait().map_err(|e| e.to_string())?;
+        if !status.success() {
+            return Err(format!("'dot' failed to render {}", file.display()));
+        }
+    }
+
+    println!("{}", file.display());
+    Ok(())
+}
+
+/// Verifies the properties of a state machine, printing a counterexample for every violated
+/// property. Properties about states the machine does not have are not applicable and skipped,
+/// the others are marked in `applied`. Returns false if any property does not hold.
+fn verify(machine: &Machine, properties: &[Property], applied: &mut [bool]) -> bool {
+    let sm = match &machine.parsed {
+        Ok(sm) => sm,
+        Err(_) => return true,
+    };
+
+    println!(
+        "{}:{}: {}",
+        machine.path.display(),
+        machine.line,
+        machine.display_name()
+    );
+
+    let mut holds = true;
+    for (property, applied) in properties.iter().zip(applied) {
+        if let Some(state) = property
+            .states()
+            .into_iter()
+            .find(|state| !sm.states.contains_key(*state))
+        {
+            println!(
+                "  not applicable: {}, `{}` is not a state of the state machine",
+                property, state
+            );
+            continue;
+        }
+        *applied = true;
+
+        match verify::verify(sm, property) {
+            Ok(Verdict::Holds) => println!("  holds: {}", property),
+            Ok(Verdict::Violated(counterexample)) => {
+                holds = false;
+                println!("  violated: {}", property);
+                match counterexample {
+                    Some(path) if path.steps.is_empty() => {
+                        println!("    counterexample: starting in {}", path.start)
+                    }
+                    Some(path) => {
+                        println!("    counterexample:");
+                        for step in &path.steps {
+                            println!("      {}", step);
+                        }
+                    }
+                    None => println!("    no sequence of events reaches it"),
+                }
+            }
+            Err(error) => {
+                holds = false;
+                println!("  error: {}: {}", property, error);
+            }
+        }
+    }
+
+    holds
+}
+
+fn run(options: Options) -> Result<bool, String> {
+    let (mut machines, diagnostics) = load(&options.paths)?;
+    if let Some(name) = &options.machine {
+        machines.retain(|machine| &machine.display_name() == name);
+        if machines.is_empty() {
+            return Err(format!("no state machine named `{}` found", name));
+        }
+    }
+
+    for diagnostic in &diagnostics {
+        eprintln!(
+            "{}:{}:{}: {}: {}",
+            diagnostic.path.display(),
+            diagnostic.line,
+            diagnostic.column,
+            diagnostic.level,
+            diagnostic.message
+        );
+    }
+
+    match &options.command {
+        Subcommand::Check => {
+            let failed = machines.iter().filter(|m| m.parsed.is_err()).count();
+            println!(
+                "checked {} state machine(s), {} with errors",
+                machines.len(),
+                failed
+            );
+        }
+        Subcommand::List => machines.iter().for_each(list),
+        Subcommand::Render { format, output } => {
+            for machine in &machines {
+                render(machine, format, output)?;
+            }
+        }
+        Subcommand::Verify { properties } => {
+            let mut holds = true;
+            let mut applied = vec![false; properties.len()];
+            for machine in &machines {
+                holds &= verify(machine, properties, &mut applied);
+            }
+            // A property which applies to no state machine most likely names a state wrongly
+            for (property, _) in properties
+                .iter()
+                .zip(&applied)
+                .filter(|(_, applied)| !**applied)
+            {
+                holds = false;
+                eprintln!(
+                    "error: {}: no state machine has all of its states",
+                    property
+                );
+            }
+            if !holds {
+                return Ok(false);
+            }
+        }
+    }
+
+    Ok(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.level != "error"))
+}
+
+fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, USAGE);
+            process::exit(2);
+        }
+    };
+
+    match run(options) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(2);
+        }
+    }
+}
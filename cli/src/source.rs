@@ -0,0 +1,236 @@
+use crate::parser::{state_machine::StateMachine, ParsedStateMachine};
+use crate::validation;
+use proc_macro2::Span;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use syn::{parse, visit::Visit};
+
+/// A `statemachine!` invocation found in a source file.
+pub struct Machine {
+    /// The file containing the invocation.
+    pub path: PathBuf,
+    /// The line of the invocation, starting at 1.
+    pub line: usize,
+    /// The parsed and validated state machine, or the errors the macro would have reported.
+    pub parsed: Result<ParsedStateMachine, parse::Error>,
+    /// The findings of lints configured to `warn`.
+    pub warnings: Vec<parse::Error>,
+}
+
+impl Machine {
+    /// A human readable name for the machine, unnamed machines are named after their location.
+    pub fn display_name(&self) -> String {
+        match self.parsed.as_ref().ok().and_then(|sm| sm.name.as_ref()) {
+            Some(name) => name.to_string(),
+            None => format!("<unnamed at line {}>", self.line),
+        }
+    }
+}
+
+/// A diagnostic attached to a location in a source file.
+pub struct Diagnostic {
+    pub path: PathBuf,
+    /// `error` or `warning`.
+    pub level: &'static str,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Splits a (possibly combined) error into one diagnostic per message. Errors without a
+    /// location of their own (emitted at the macro call site) are reported at `fallback_line`.
+    pub fn from_error(
+        path: &Path,
+        fallback_line: usize,
+        level: &'static str,
+        error: &parse::Error,
+    ) -> Vec<Self> {
+        let call_site = Span::call_site().start();
+        error
+            .clone()
+            .into_iter()
+            .map(|error| {
+                let start = error.span().start();
+                let (line, column) = if start == call_site {
+                    (fallback_line, 1)
+                } else {
+                    (start.line, start.column + 1)
+                };
+                Diagnostic {
+                    path: path.to_path_buf(),
+                    level,
+                    line,
+                    column,
+                    message: error.to_string(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Collects all Rust source files in `paths`, descending into directories. Hidden directories and
+/// `target` directories are skipped.
+pub fn collect_files(paths: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            collect_dir(path, &mut files)?;
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+fn collect_dir(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    // Keep the output stable between runs.
+    entries.sort();
+
+    for path in entries {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                collect_dir(&path, files)?;
+            }
+        } else if name.ends_with(".rs") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Finds, parses and validates every `statemachine!` invocation in the given source code.
+pub fn find_machines(path: &Path, source: &str) -> Result<Vec<Machine>, parse::Error> {
+    let file = syn::parse_file(source)?;
+
+    let mut finder = MacroFinder { found: Vec::new() };
+    finder.visit_file(&file);
+
+    Ok(finder
+        .found
+        .into_iter()
+        .map(|mac| {
+            let line = mac.path.segments.last().unwrap().ident.span().start().line;
+            let mut warnings = Vec::new();
+            let parsed = syn::parse2::<StateMachine>(mac.tokens)
+                .and_then(ParsedStateMachine::new)
+                .and_then(|sm| {
+                    let findings = validation::validate(&sm)?;
+                    warnings = findings.into_iter().map(|(_, warning)| warning).collect();
+                    Ok(sm)
+                });
+            Machine {
+                path: path.to_path_buf(),
+                line,
+                parsed,
+                warnings,
+            }
+        })
+        .collect())
+}
+
+struct MacroFinder {
+    found: Vec<syn::Macro>,
+}
+
+impl<'ast> Visit<'ast> for MacroFinder {
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        if mac
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "statemachine")
+        {
+            self.found.push(mac.clone());
+        }
+        syn::visit::visit_macro(self, mac);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_nested_invocations() {
+        let source = r#"
+            use smlang::statemachine;
+
+            statemachine! {
+                name: Top,
+                transitions: {
+                    *Idle + Start = Running,
+                }
+            }
+
+            mod inner {
+                fn build() {
+                    smlang::statemachine! {
+                        transitions: {
+                            *A + Go = B,
+                        }
+                    }
+                }
+            }
+        "#;
+
+        let machines = find_machines(Path::new("lib.rs"), source).unwrap();
+        assert_eq!(machines.len(), 2);
+        assert_eq!(machines[0].display_name(), "Top");
+        assert_eq!(machines[0].line, 4);
+        assert!(machines[1].parsed.is_ok());
+        assert_eq!(machines[1].line, 13);
+    }
+
+    #[test]
+    fn reports_validation_errors() {
+        let source = r#"
+            statemachine! {
+                transitions: {
+                    *State1 + Event1 = State2,
+                    State1 + Event1 = State3,
+                }
+            }
+        "#;
+
+        let machines = find_machines(Path::new("lib.rs"), source).unwrap();
+        let error = machines[0].parsed.as_ref().err().unwrap();
+        let diagnostics =
+            Diagnostic::from_error(Path::new("lib.rs"), machines[0].line, "error", error);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (5, 21));
+        assert!(diagnostics[0]
+            .message
+            .contains("State and event combination specified multiple times"));
+        assert_eq!((diagnostics[1].line, diagnostics[1].column), (4, 22));
+        assert!(diagnostics[1].message.contains("first defined here"));
+    }
+
+    #[test]
+    fn reports_lint_warnings() {
+        let source = r#"
+            statemachine! {
+                lints: { unreachable_state: warn },
+                transitions: {
+                    *State1 + Event1 = State2,
+                    State3 + Event1 = State1,
+                }
+            }
+        "#;
+
+        let machines = find_machines(Path::new("lib.rs"), source).unwrap();
+        assert!(machines[0].parsed.is_ok());
+        assert_eq!(machines[0].warnings.len(), 1);
+        assert!(machines[0].warnings[0]
+            .to_string()
+            .contains("State `State3` is unreachable"));
+    }
+}
@@ -0,0 +1,356 @@
+//! Exhaustive verification of properties of a state machine. Every guard is a free input, so all
+//! results of the guards of every state and event combination are explored.
+
+use crate::parser::transition::visit_guards;
+use crate::parser::ParsedStateMachine;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/// The maximum number of guards of a state and event combination, all 2^n combinations of their
+/// results are explored.
+const MAX_GUARDS: usize = 16;
+
+/// A property of the states a state machine can reach.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Property {
+    /// The state can be reached from an initial state.
+    Reachable(String),
+    /// The state can be reached from every reachable state, i.e. it can never become
+    /// unreachable.
+    AlwaysReachable(String),
+    /// Every path from an initial state to `state` passes through `via`.
+    Requires { state: String, via: String },
+}
+
+impl Property {
+    /// Returns the states the property is about.
+    pub fn states(&self) -> Vec<&str> {
+        match self {
+            Property::Reachable(state) | Property::AlwaysReachable(state) => vec![state],
+            Property::Requires { state, via } => vec![state, via],
+        }
+    }
+}
+
+impl fmt::Display for Property {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Property::Reachable(state) => write!(f, "`{}` is reachable", state),
+            Property::AlwaysReachable(state) => {
+                write!(f, "`{}` can be reached from every reachable state", state)
+            }
+            Property::Requires { state, via } => {
+                write!(f, "`{}` is only reached through `{}`", state, via)
+            }
+        }
+    }
+}
+
+/// A transition to another state, with the results of the guards which lead to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub from: String,
+    pub event: String,
+    pub guards: Vec<(String, bool)>,
+    pub to: String,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} + {}", self.from, self.event)?;
+        if !self.guards.is_empty() {
+            let guards: Vec<_> = self
+                .guards
+                .iter()
+                .map(|(guard, passed)| format!("{}{}", if *passed { "" } else { "!" }, guard))
+                .collect();
+            write!(f, " [{}]", guards.join(", "))?;
+        }
+        write!(f, " = {}", self.to)
+    }
+}
+
+/// A sequence of transitions from an initial state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    pub start: String,
+    pub steps: Vec<Step>,
+}
+
+impl Path {
+    fn end(&self) -> &str {
+        self.steps.last().map_or(&self.start, |step| &step.to)
+    }
+}
+
+/// The result of verifying a property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Holds,
+    /// The property does not hold, with a counterexample if there is one. A state which is not
+    /// reachable has none.
+    Violated(Option<Path>),
+}
+
+/// Verifies a property of a state machine.
+pub fn verify(sm: &ParsedStateMachine, property: &Property) -> Result<Verdict, String> {
+    for state in property.states() {
+        if !sm.states.contains_key(state) {
+            return Err(format!("`{}` is not a state of the state machine", state));
+        }
+    }
+    let graph = transitions(sm)?;
+
+    let verdict = match property {
+        Property::Reachable(state) => {
+            if explore(sm, &graph, None)
+                .iter()
+                .any(|path| path.end() == state)
+            {
+                Verdict::Holds
+            } else {
+                Verdict::Violated(None)
+            }
+        }
+        Property::AlwaysReachable(state) => {
+            // The states from which `state` can be reached, found backwards from it
+            let mut reaching: HashSet<&str> = HashSet::new();
+            reaching.insert(state);
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for step in graph.values().flatten() {
+                    if reaching.contains(step.to.as_str()) && reaching.insert(&step.from) {
+                        changed = true;
+                    }
+                }
+            }
+
+            match explore(sm, &graph, None)
+                .into_iter()
+                .find(|path| !reaching.contains(path.end()))
+            {
+                Some(path) => Verdict::Violated(Some(path)),
+                None => Verdict::Holds,
+            }
+        }
+        Property::Requires { state, via } => {
+            match explore(sm, &graph, Some(via))
+                .into_iter()
+                .find(|path| path.end() == state && state != via)
+            {
+                Some(path) => Verdict::Violated(Some(path)),
+                None => Verdict::Holds,
+            }
+        }
+    };
+
+    Ok(verdict)
+}
+
+/// Returns the transitions to other states of every state, for every result of the guards.
+/// Transitions to the same state, ignored and rejected events and events whose guards all fail
+/// leave the state unchanged and are left out.
+fn transitions(sm: &ParsedStateMachine) -> Result<HashMap<String, Vec<Step>>, String> {
+    let mut graph = HashMap::new();
+
+    for (state, event_mappings) in &sm.states_events_mapping {
+        let mut event_mappings: Vec<_> = event_mappings.values().collect();
+        event_mappings.sort_by_key(|event_mapping| event_mapping.event.to_string());
+
+        let mut steps: Vec<Step> = Vec::new();
+        for event_mapping in event_mappings {
+            let event = event_mapping.event.to_string();
+
+            let mut guards: Vec<String> = Vec::new();
+            for transition in &event_mapping.transitions {
+                if let Some(guard_expression) = &transition.guard {
+                    visit_guards(guard_expression, |guard| {
+                        let name = guard.ident.to_string();
+                        if !guards.contains(&name) {
+                            guards.push(name);
+                        }
+                        Ok(())
+                    })
+                    .map_err(|e| e.to_string())?;
+                }
+            }
+            if guards.len() > MAX_GUARDS {
+                return Err(format!(
+                    "`{} + {}` depends on {} guards, at most {} can be verified",
+                    state,
+                    event,
+                    guards.len(),
+                    MAX_GUARDS
+                ));
+            }
+
+            // Bit `n` of `results` is the result of guard `n`, the first enabled transition is
+            // taken
+            for results in 0..1usize << guards.len() {
+                let taken =
+                    event_mapping
+                        .transitions
+                        .iter()
+                        .find(|transition| match &transition.guard {
+                            Some(guard_expression) => guard_expression.eval(&|guard| {
+                                let n = guards.iter().position(|name| guard.ident == name).unwrap();
+                                results & (1 << n) != 0
+                            }),
+                            None => true,
+                        });
+                let to = match taken {
+                    Some(transition) if transition.is_transition() => {
+                        transition.out_state.to_string()
+                    }
+                    _ => continue,
+                };
+                if &to == state
+                    || !sm.states.contains_key(&to)
+                    || steps
+                        .iter()
+                        .any(|step| step.event == event && step.to == to)
+                {
+                    continue;
+                }
+
+                steps.push(Step {
+                    from: state.clone(),
+                    event: event.clone(),
+                    guards: guards
+                        .iter()
+                        .enumerate()
+                        .map(|(n, guard)| (guard.clone(), results & (1 << n) != 0))
+                        .collect(),
+                    to,
+                });
+            }
+        }
+
+        graph.insert(state.clone(), steps);
+    }
+
+    Ok(graph)
+}
+
+/// Returns the shortest path to every state reachable from the initial states without passing
+/// through `avoid`, in breadth-first order.
+fn explore(
+    sm: &ParsedStateMachine,
+    graph: &HashMap<String, Vec<Step>>,
+    avoid: Option<&str>,
+) -> Vec<Path> {
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    for state in std::iter::once(&sm.starting_state).chain(sm.entry_states.iter()) {
+        let state = state.to_string();
+        if Some(state.as_str()) != avoid && visited.insert(state.clone()) {
+            queue.push_back(Path {
+                start: state,
+                steps: Vec::new(),
+            });
+        }
+    }
+
+    let mut paths = Vec::new();
+    while let Some(path) = queue.pop_front() {
+        for step in graph.get(path.end()).into_iter().flatten() {
+            if Some(step.to.as_str()) != avoid && visited.insert(step.to.clone()) {
+                let mut next = path.clone();
+                next.steps.push(step.clone());
+                queue.push_back(next);
+            }
+        }
+        paths.push(path);
+    }
+
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::find_machines;
+    use std::path::Path as FilePath;
+
+    const SOURCE: &str = r#"
+        statemachine! {
+            transitions: {
+                *Idle + Arm [is_ready] = Armed,
+                Idle + Arm [!is_ready && is_forced] = Active,
+                Armed + Go = Active,
+                Active + Stop = Idle,
+                Active + Fail = Fault,
+                Armed + Fail = Fault,
+                Fault + Reset [is_safe] = Idle,
+                Fault + Halt = Halted,
+            }
+        }
+    "#;
+
+    fn check(property: Property) -> Verdict {
+        let machines = find_machines(FilePath::new("lib.rs"), SOURCE).unwrap();
+        let sm = machines[0].parsed.as_ref().unwrap();
+        verify(sm, &property).unwrap()
+    }
+
+    #[test]
+    fn reachable_states() {
+        assert_eq!(check(Property::Reachable("Halted".into())), Verdict::Holds);
+    }
+
+    #[test]
+    fn always_reachable_counterexample() {
+        assert_eq!(
+            check(Property::AlwaysReachable("Fault".into())),
+            Verdict::Violated(Some(Path {
+                start: "Idle".into(),
+                steps: vec![
+                    Step {
+                        from: "Idle".into(),
+                        event: "Arm".into(),
+                        guards: vec![("is_ready".into(), true), ("is_forced".into(), false)],
+                        to: "Armed".into(),
+                    },
+                    Step {
+                        from: "Armed".into(),
+                        event: "Fail".into(),
+                        guards: vec![],
+                        to: "Fault".into(),
+                    },
+                    Step {
+                        from: "Fault".into(),
+                        event: "Halt".into(),
+                        guards: vec![],
+                        to: "Halted".into(),
+                    },
+                ],
+            }))
+        );
+    }
+
+    #[test]
+    fn required_state_counterexample() {
+        let verdict = check(Property::Requires {
+            state: "Active".into(),
+            via: "Armed".into(),
+        });
+        let path = match verdict {
+            Verdict::Violated(Some(path)) => path,
+            verdict => panic!("unexpected verdict {:?}", verdict),
+        };
+        assert_eq!(path.steps.len(), 1);
+        assert_eq!(
+            path.steps[0].to_string(),
+            "Idle + Arm [!is_ready, is_forced] = Active"
+        );
+
+        assert_eq!(
+            check(Property::Requires {
+                state: "Halted".into(),
+                via: "Fault".into(),
+            }),
+            Verdict::Holds
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Runs the `verify` command of the `smlang` binary on a file with two state machines.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const SOURCE: &str = r#"
+statemachine! {
+    name: Door,
+    transitions: {
+        *Closed + Open = Opened,
+        Opened + Close = Closed,
+    }
+}
+
+statemachine! {
+    name: Lamp,
+    transitions: {
+        *Off + Switch = On,
+        On + Switch = Off,
+        On + Break = Broken,
+    }
+}
+"#;
+
+fn write_source(name: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&path, SOURCE).unwrap();
+    path
+}
+
+fn smlang(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_smlang"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn properties_apply_to_the_machines_with_their_states() {
+    let path = write_source("two_machines.rs");
+    let output = smlang(&[
+        "verify",
+        "--reachable",
+        "Opened",
+        "--always-reachable",
+        "Off",
+        path.to_str().unwrap(),
+    ]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert_eq!(output.status.code(), Some(1), "{}", stdout);
+    assert!(stdout.contains("  holds: `Opened` is reachable"));
+    assert!(stdout.contains(
+        "  not applicable: `Opened` is reachable, `Opened` is not a state of the state machine"
+    ));
+    assert!(stdout.contains("  violated: `Off` can be reached from every reachable state"));
+    assert!(!stdout.contains("error"));
+}
+
+#[test]
+fn machine_filter() {
+    let path = write_source("two_machines_filter.rs");
+    let output = smlang(&[
+        "verify",
+        "--machine",
+        "Door",
+        "--always-reachable",
+        "Closed",
+        path.to_str().unwrap(),
+    ]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("Door"));
+    assert!(!stdout.contains("Lamp"));
+
+    let output = smlang(&["verify", "--reachable", "Ajar", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("`Ajar` is reachable: no state machine has all of its states"));
+
+    let output = smlang(&["list", "-m", "Fan", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -31,7 +31,7 @@ pub struct Context;
 impl StateMachineContext for Context {
     // Guard1 has access to the data from Event1
     fn guard1(&self, event_data: &MyEventData) -> Result<bool, ()> {
-        Ok(event_data.0 % 2 == 0)
+        Ok(event_data.0.is_multiple_of(2))
     }
 
     // Action1 has access to the data from Event1, and need to return the state data for State2
@@ -42,7 +42,7 @@ impl StateMachineContext for Context {
 
     // Guard2 has access to the data from State2
     fn guard2(&self, state_data: &MyStateData) -> Result<bool, ()> {
-        Ok(state_data.0 % 2 == 0)
+        Ok(state_data.0.is_multiple_of(2))
     }
 
     // Action2 has access to the data from State2
@@ -3,10 +3,17 @@
 use crate::parser::transition::visit_guards;
 use crate::parser::{lifetimes::Lifetimes, AsyncIdent, ParsedStateMachine};
 use proc_macro2::{Ident, Span, TokenStream};
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, quote_spanned};
+use std::collections::HashMap;
 use syn::Type;
 
 pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
+    // The event log is stored in a `Vec`, which smlang only provides with its `std` feature
+    if let (true, Some(span), false) = (sm.replay, sm.replay_span, cfg!(feature = "std")) {
+        return syn::Error::new(span, "`replay: true` requires the `std` feature of smlang")
+            .to_compile_error();
+    }
+
     let (sm_name, sm_name_span) = sm
         .name
         .as_ref()
@@ -15,6 +22,7 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
     let states_type_name = format_ident!("{sm_name}States", span = sm_name_span);
     let events_type_name = format_ident!("{sm_name}Events", span = sm_name_span);
     let error_type_name = format_ident!("{sm_name}Error", span = sm_name_span);
+    let reject_reason_type_name = format_ident!("{sm_name}RejectReason", span = sm_name_span);
     let state_machine_type_name = format_ident!("{sm_name}StateMachine", span = sm_name_span);
     let state_machine_context_type_name =
         format_ident!("{sm_name}StateMachineContext", span = sm_name_span);
@@ -67,8 +75,8 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
     let transitions = &sm.states_events_mapping;
 
     let in_states: Vec<_> = transitions
-        .iter()
-        .map(|(name, _)| {
+        .keys()
+        .map(|name| {
             let state_name = sm.states.get(name).unwrap();
 
             match sm.state_data.data_types.get(name) {
@@ -87,8 +95,8 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
         .collect();
 
     let events: Vec<Vec<_>> = transitions
-        .iter()
-        .map(|(_, value)| {
+        .values()
+        .map(|value| {
             value
                 .iter()
                 .map(|(name, value)| {
@@ -100,6 +108,13 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
                                 #value
                             }
                         }
+                        // The event is borrowed by the entry and exit functions or
+                        // `on_transition`, so the event data is not moved out of it
+                        Some(_) if sm.borrows_event() => {
+                            quote! {
+                                #value(ref event_data)
+                            }
+                        }
                         Some(_) => {
                             quote! {
                                 #value(event_data)
@@ -144,14 +159,24 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
         })
         .collect();
 
+    let transition_list: Vec<Vec<_>> = transitions
+        .values()
+        .map(|event_mappings| {
+            event_mappings
+                .values()
+                .map(|event_mapping| event_mapping.transitions.iter().collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
     let action_parameters: Vec<Vec<_>> = transitions
         .iter()
         .map(|(name, value)| {
             let state_name = &sm.states.get(name).unwrap().to_string();
 
             value
-                .iter()
-                .map(|(name, _)| {
+                .keys()
+                .map(|name| {
                     let state_data = match sm.state_data.data_types.get(state_name) {
                         Some(Type::Reference(_)) => quote! { state_data },
                         Some(_) => quote! { &state_data },
@@ -159,6 +184,7 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
                     };
 
                     let event_data = match sm.event_data.data_types.get(name) {
+                        Some(_) if sm.borrows_event() => quote! { event_data.clone() },
                         Some(_) => quote! { event_data },
                         None => quote! {},
                     };
@@ -179,8 +205,8 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
             let state_name = &sm.states.get(name).unwrap().to_string();
 
             value
-                .iter()
-                .map(|(name, _)| {
+                .keys()
+                .map(|name| {
                     let state_data = match sm.state_data.data_types.get(state_name) {
                         Some(Type::Reference(_)) => quote! { state_data },
                         Some(_) => quote! { &state_data },
@@ -257,6 +283,36 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
 
     let mut entries_exits = proc_macro2::TokenStream::new();
 
+    // With `mock: true`, the same functions are implemented for `smlang::mock::Mock`
+    let mut mock_functions = proc_macro2::TokenStream::new();
+
+    // With `async_hooks`, the entry and exit functions and the transition callback are async and
+    // fallible
+    let (hook_async, hook_return_type, hook_body) = match (sm.async_hooks, sm.async_send) {
+        (false, _) => (quote! {}, quote! {}, quote! { {} }),
+        (true, false) => (
+            quote! { async },
+            quote! { -> Result<(), #custom_error> },
+            quote! { { Ok(()) } },
+        ),
+        (true, true) => (
+            quote! {},
+            quote! { -> impl core::future::Future<Output = Result<(), #custom_error>> + Send },
+            quote! { { async { Ok(()) } } },
+        ),
+    };
+
+    // With `entry_exit_event`, the entry and exit functions also receive the triggering event
+    let (hook_event, hook_event_lifetimes) = if sm.entry_exit_event {
+        let event_lifetimes = &sm.event_data.all_lifetimes;
+        (
+            quote! { event: &#events_type_name <#event_lifetimes>, },
+            event_lifetimes.clone(),
+        )
+    } else {
+        (quote! {}, Lifetimes::new())
+    };
+
     for (state, event_mappings) in transitions.iter() {
         // create the state data token stream
         let state_data = match sm.state_data.data_types.get(state) {
@@ -265,19 +321,52 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
             None => quote! {},
         };
 
+        // the entry and exit functions receive the data of the state
+        let mut hook_lifetimes = sm
+            .state_data
+            .lifetimes
+            .get(state)
+            .cloned()
+            .unwrap_or_default();
+        hook_lifetimes.extend(&hook_event_lifetimes);
+
         let entry_ident = format_ident!("on_entry_{}", string_morph::to_snake_case(state));
         let state_name = format!("[{}::{}]", states_type_name, state);
         entries_exits.extend(quote! {
             #[doc = concat!("Called on entry to ", #state_name)]
             #[inline(always)]
-            fn #entry_ident(&mut self) {}
+            #[allow(unused_variables)]
+            #hook_async fn #entry_ident <#hook_lifetimes> (&mut self, #state_data #hook_event) #hook_return_type #hook_body
         });
         let exit_ident = format_ident!("on_exit_{}", string_morph::to_snake_case(state));
         entries_exits.extend(quote! {
             #[doc = concat!("Called on exit from ", #state_name)]
             #[inline(always)]
-            fn #exit_ident(&mut self) {}
+            #[allow(unused_variables)]
+            #hook_async fn #exit_ident <#hook_lifetimes> (&mut self, #state_data #hook_event) #hook_return_type #hook_body
         });
+        if sm.mock {
+            let (entry_body, exit_body) = match (sm.async_hooks, sm.async_send) {
+                (false, _) => (
+                    quote! { { self.record(::smlang::mock::Call::Entry(#state)) } },
+                    quote! { { self.record(::smlang::mock::Call::Exit(#state)) } },
+                ),
+                (true, false) => (
+                    quote! { { self.record(::smlang::mock::Call::Entry(#state)); Ok(()) } },
+                    quote! { { self.record(::smlang::mock::Call::Exit(#state)); Ok(()) } },
+                ),
+                (true, true) => (
+                    quote! { { self.record(::smlang::mock::Call::Entry(#state)); async { Ok(()) } } },
+                    quote! { { self.record(::smlang::mock::Call::Exit(#state)); async { Ok(()) } } },
+                ),
+            };
+            mock_functions.extend(quote! {
+                #[allow(unused_variables)]
+                #hook_async fn #entry_ident <#hook_lifetimes> (&mut self, #state_data #hook_event) #hook_return_type #entry_body
+                #[allow(unused_variables)]
+                #hook_async fn #exit_ident <#hook_lifetimes> (&mut self, #state_data #hook_event) #hook_return_type #exit_body
+            });
+        }
 
         for (event, event_mapping) in event_mappings {
             for transition in &event_mapping.transitions {
@@ -315,6 +404,7 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
                 if let Some(guard_expression) = &transition.guard {
                     visit_guards(guard_expression,|guard| {
                         let is_async = guard.is_async;
+                        let returns_future = is_async && sm.async_send;
                         let guard = &guard.ident;
                         let event_data = match sm.event_data.data_types.get(event) {
                             Some(et @ Type::Reference(_)) => quote! { event_data: #et },
@@ -325,12 +415,22 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
                         // Only add the guard if it hasn't been added before
                         if !guard_set.iter().any(|g| g == guard) {
                             guard_set.push(guard.clone());
-                            let is_async = if is_async { quote!{ async } } else { quote!{ } };
+                            let (is_async, return_type) = async_signature(is_async, sm.async_send, quote! { Result<bool,#custom_error> });
                             guard_list.extend(quote! {
                             #[allow(missing_docs)]
                             #[allow(clippy::result_unit_err)]
-                            #is_async fn #guard <#all_lifetimes> (&self, #temporary_context #state_data #event_data) -> Result<bool,#custom_error>;
+                            #is_async fn #guard <#all_lifetimes> (&self, #temporary_context #state_data #event_data) -> #return_type;
                         });
+                            if sm.mock {
+                                let guard_name = guard.to_string();
+                                let body = mock_body(returns_future, quote! {
+                                    Ok(::smlang::mock::Mock::guard(self, #guard_name))
+                                });
+                                mock_functions.extend(quote! {
+                                    #[allow(unused_variables)]
+                                    #is_async fn #guard <#all_lifetimes> (&self, #temporary_context #state_data #event_data) -> #return_type #body
+                                });
+                            }
                         };
                         Ok(())
                     }).unwrap();
@@ -342,11 +442,6 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
                     is_async,
                 }) = &transition.action
                 {
-                    let is_async = if *is_async {
-                        quote! { async }
-                    } else {
-                        quote! {}
-                    };
                     let return_type = if let Some(output_data) = sm
                         .state_data
                         .data_types
@@ -370,11 +465,34 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
                     // Only add the action if it hasn't been added before
                     if !action_set.iter().any(|a| a == action) {
                         action_set.push(action.clone());
+                        let returns_future = *is_async && sm.async_send;
+                        let (is_async, return_type) =
+                            async_signature(*is_async, sm.async_send, return_type);
                         action_list.extend(quote! {
                             #[allow(missing_docs)]
                             #[allow(clippy::unused_unit)]
                             #is_async fn #action <#all_lifetimes> (&mut self, #temporary_context #state_data #event_data) -> #return_type;
                         });
+                        if sm.mock {
+                            let action_name = action.to_string();
+                            let has_data = sm
+                                .state_data
+                                .data_types
+                                .contains_key(&transition.out_state.to_string());
+                            let value = if has_data {
+                                quote! { Ok(::smlang::mock::Mock::action(self, #action_name)) }
+                            } else {
+                                quote! {
+                                    self.record(::smlang::mock::Call::Action(#action_name));
+                                    Ok(())
+                                }
+                            };
+                            let body = mock_body(returns_future, value);
+                            mock_functions.extend(quote! {
+                                #[allow(unused_variables)]
+                                #is_async fn #action <#all_lifetimes> (&mut self, #temporary_context #state_data #event_data) -> #return_type #body
+                            });
+                        }
                     }
                 }
             }
@@ -390,29 +508,54 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
         }
     };
 
-    let mut is_async_state_machine = false;
+    let error_type = if sm.custom_error {
+        quote! {
+            #error_type_name<<T as #state_machine_context_type_name>::Error>
+        }
+    } else {
+        quote! {#error_type_name}
+    };
+
+    let mut is_async_state_machine = sm.async_hooks;
+
+    // Whether any transition can be interrupted by dropping the future of `process_event` after
+    // the exit function was called and before the entry function returned
+    let mut has_interruptible_transitions = false;
+
+    // Calls of the entry and exit functions and the transition callback
+    let (hook_await, exit_failed, callback_failed, entry_failed) = if sm.async_hooks {
+        (
+            quote! { .await },
+            quote! { .map_err(#error_type_name::ExitFailed)? },
+            quote! { .map_err(#error_type_name::TransitionCallbackFailed)? },
+            quote! { .map_err(#error_type_name::EntryFailed)? },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {}, quote! {})
+    };
 
     // Create the code blocks inside the switch cases
     let code_blocks: Vec<Vec<_>> = guards
         .iter()
         .zip(
-            actions
+            transition_list.iter().zip(actions
                 .iter()
-                .zip(in_states.iter().zip(out_states.iter().zip(action_parameters.iter().zip(guard_parameters.iter())))),
+                .zip(in_states.iter().zip(out_states.iter().zip(action_parameters.iter().zip(guard_parameters.iter()))))),
         )
         .map(
-            |(guards, (actions, (in_state, (out_states, (action_parameters, guard_parameters)))))| {
+            |(guards, (transition_list, (actions, (in_state, (out_states, (action_parameters, guard_parameters))))))| {
                 guards
                     .iter()
                     .zip(
-                        actions
+                        transition_list.iter().zip(actions
                             .iter()
-                            .zip(out_states.iter().zip(action_parameters.iter().zip(guard_parameters.iter()))),
+                            .zip(out_states.iter().zip(action_parameters.iter().zip(guard_parameters.iter())))),
                     )
-                    .map(|(guard, (action, (out_state, (action_params, guard_params))))| {
+                    .map(|(guard, (transition_list, (action, (out_state, (action_params, guard_params)))))| {
                         let streams: Vec<TokenStream> =
                             guard.iter()
-                            .zip(action.iter().zip(out_state)).map(|(guard, (action,out_state))| {
+                            .zip(transition_list.iter().zip(action.iter().zip(out_state))).map(|(guard, (transition, (action,out_state)))| {
+                                let index = transition.index;
                                 let binding = out_state.to_string();
                                 let out_state_string = binding.split('(').next().unwrap().trim();
                                 let binding = in_state.to_string();
@@ -424,21 +567,106 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
                                 let (is_async_action, action_code) = generate_action(action, &temporary_context_call, action_params, &error_type_name);
                                 is_async_state_machine |= is_async_action;
 
-                                let transition = if in_state_string == out_state_string {
+                                // The entry and exit functions receive the data of their state,
+                                // and with `entry_exit_event` the event
+                                let hook_event = if sm.entry_exit_event {
+                                    quote! { &event }
+                                } else {
+                                    quote! {}
+                                };
+                                let exit_call = if sm.state_data.data_types.contains_key(in_state_string) {
+                                    quote! { self.context.#exit_ident(state_data, #hook_event) }
+                                } else {
+                                    quote! { self.context.#exit_ident(#hook_event) }
+                                };
+                                let entry_call = if sm.state_data.data_types.contains_key(out_state_string) {
+                                    let out_state_ident = &sm.states[out_state_string];
+                                    quote! {
+                                        match self.state {
+                                            #states_type_name::#out_state_ident(ref state_data) => {
+                                                self.context.#entry_ident(state_data, #hook_event) #hook_await #entry_failed;
+                                            }
+                                            _ => unreachable!(),
+                                        }
+                                    }
+                                } else {
+                                    quote! { self.context.#entry_ident(#hook_event) #hook_await #entry_failed; }
+                                };
+
+                                // With `on_transition`, every transition is recorded before the
+                                // state changes
+                                let record = |internal: bool| if sm.on_transition {
+                                    quote! {
+                                        self.context.on_transition(&::smlang::TransitionRecord {
+                                            from: &self.state,
+                                            to: &out_state,
+                                            event: &event,
+                                            guard: Self::TRANSITIONS[#index].guard,
+                                            action: Self::TRANSITIONS[#index].action,
+                                            internal: #internal,
+                                        });
+                                    }
+                                } else {
+                                    quote! {}
+                                };
+                                let record_internal = record(true);
+                                let record_external = record(false);
+                                let count_transition = if sm.metrics {
+                                    quote! { self.metrics.record_transition(#index); }
+                                } else {
+                                    quote! {}
+                                };
+                                let count_transition = if cfg!(feature = "coverage") {
+                                    quote! {
+                                        #count_transition
+                                        Self::coverage_marks().mark(#index);
+                                    }
+                                } else {
+                                    count_transition
+                                };
+
+                                let transition = if transition.ignore {
+                                    // The event is explicitly ignored => nothing to do
+                                    quote!{
+                                            return Ok(&self.state);
+                                        }
+                                } else if let Some(reason) = &transition.reject {
+                                    // The event is explicitly rejected => report the reason
+                                    quote!{
+                                            return Err(#error_type_name::Rejected(#reject_reason_type_name::#reason));
+                                        }
+                                } else if in_state_string == out_state_string {
                                     // Stay in the same state => no need to call on_entry/on_exit
                                     quote!{
                                             #action_code
-                                            self.state = #states_type_name::#out_state;
+                                            let out_state = #states_type_name::#out_state;
+                                            #record_internal
+                                            self.state = out_state;
+                                            self.last_transition = Some(#index);
+                                            #count_transition
                                             return Ok(&self.state);
                                         }
                                 } else {
+                                    // When the transition awaits between exiting the current state
+                                    // and entering the new one, mark it as in progress, so
+                                    // dropping the future is detected by the next call
+                                    let mark_interrupted = if sm.async_hooks || is_async_action {
+                                        has_interruptible_transitions = true;
+                                        quote! { self.interrupted_transition = Some(#index); }
+                                    } else {
+                                        quote! {}
+                                    };
                                     quote!{
-                                            self.context.#exit_ident();
+                                            #mark_interrupted
+                                            #exit_call #hook_await #exit_failed;
                                             #action_code
                                             let out_state = #states_type_name::#out_state;
-                                            self.context().transition_callback(&self.state, &out_state);
+                                            self.context().transition_callback(&self.state, &out_state) #hook_await #callback_failed;
+                                            #record_external
                                             self.state = out_state;
-                                            self.context.#entry_ident();
+                                            self.last_transition = Some(#index);
+                                            #count_transition
+                                            #entry_call
                                             return Ok(&self.state);
                                         }
                                 };
@@ -451,17 +679,42 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
                                         } else {
                                             quote! {}
                                         };
-                                        quote! {
+                                        let call = quote! {
                                             self.context.#guard_ident(#temporary_context_call #guard_params) #guard_await .map_err(#error_type_name::GuardFailed)?
+                                        };
+                                        // With `replay: true`, the result of every guard is
+                                        // recorded, and taken from the tape while replaying
+                                        if sm.replay {
+                                            quote! {
+                                                {
+                                                    let passed = match self.guard_tape.next_guard() {
+                                                        Some(passed) => passed,
+                                                        None => #call,
+                                                    };
+                                                    if let Some(event_log) = &mut self.event_log {
+                                                        event_log.record_guard(passed);
+                                                    }
+                                                    passed
+                                                }
+                                            }
+                                        } else {
+                                            call
                                         }
                                     });
+                                    let guard_string = expr.as_written();
+                                    let trace_guard = if cfg!(feature = "tracing") {
+                                        quote! { ::smlang::tracing::debug!(guard = #guard_string, passed = guard_passed, "guard"); }
+                                    } else {
+                                        quote! {}
+                                    };
                                     quote! {
                                         // This #guard_expression contains a boolean expression of guard functions
                                         // Each guard function has Result<bool,_> return type.
                                         // For example, [ f && !g ] will expand into
                                         //  self.context.f()? && !self.context.g()?
                                         let guard_passed = #guard_expression;
-                                        self.context.log_guard(stringify!(#guard_expression), guard_passed);
+                                        self.context.log_guard(#guard_string, guard_passed);
+                                        #trace_guard
 
                                         // If the guard passed, we transition immediately.
                                         // Otherwise, there may be a later transition that passes,
@@ -486,35 +739,393 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
         )
         .collect();
 
+    let state_lifetimes = &sm.state_data.all_lifetimes;
+
+    let event_lifetimes = &sm.event_data.all_lifetimes;
+
+    // lifetimes that exists in #events_type_name but not in #states_type_name
+    let event_unique_lifetimes = event_lifetimes - state_lifetimes;
+
+    // Only state machines with interruptible transitions track them
+    let (
+        interrupted_field,
+        interrupted_init,
+        interrupted_error,
+        interrupted_check,
+        interrupted_clear,
+        interrupted_methods,
+    ) = if has_interruptible_transitions {
+        (
+            quote! { interrupted_transition: Option<usize>, },
+            quote! { interrupted_transition: None, },
+            quote! {
+                /// When a transition was interrupted by dropping the future of `process_event`,
+                /// until `recover` is called.
+                TransitionInterrupted,
+            },
+            quote! {
+                if self.interrupted_transition.is_some() {
+                    return Err(#error_type_name::TransitionInterrupted);
+                }
+            },
+            // The transition completed or failed, so it was not interrupted
+            quote! { self.interrupted_transition = None; },
+            quote! {
+                /// Returns the transition which was interrupted by dropping the future of
+                /// `process_event` after the exit function of its source state was called and
+                /// before the entry function of its target state returned, if any.
+                ///
+                /// The state is changed after the action and the transition callback, so the
+                /// current state shows how far the transition got. Until `recover` is called,
+                /// `process_event` returns `Err(TransitionInterrupted)`.
+                #[inline(always)]
+                pub fn interrupted_transition(&self) -> Option<&'static ::smlang::TransitionInfo> {
+                    self.interrupted_transition.map(|index| &Self::TRANSITIONS[index])
+                }
+
+                /// Returns true if a transition was interrupted, see `interrupted_transition`.
+                #[inline(always)]
+                pub fn is_transition_interrupted(&self) -> bool {
+                    self.interrupted_transition.is_some()
+                }
+
+                /// Accepts events again after a transition was interrupted, keeping the current
+                /// state. Any cleanup of the context has to be done before.
+                #[inline(always)]
+                pub fn recover(&mut self) -> &#states_type_name <#state_lifetimes> {
+                    self.interrupted_transition = None;
+                    &self.state
+                }
+            },
+        )
+    } else {
+        (
+            quote! {},
+            quote! {},
+            quote! {},
+            quote! {},
+            quote! {},
+            quote! {},
+        )
+    };
+
+    // The kinds of the states and events, without their data, which can always be formatted
+    let state_kind_type_name = format_ident!("{sm_name}StateKind", span = sm_name_span);
+    let event_kind_type_name = format_ident!("{sm_name}EventKind", span = sm_name_span);
+
+    // With `history: N`, the result of every call of `process_event` is recorded in a ring buffer
+    let (
+        history_field,
+        history_init,
+        history_start,
+        history_record,
+        history_methods,
+        history_timestamp,
+    ) = if let Some(size) = sm.history {
+        let (timestamp_type, timestamp, timestamp_fn) = match &sm.history_timestamp_type {
+            Some(timestamp_type) => (
+                quote! { #timestamp_type },
+                quote! { self.context.history_timestamp() },
+                quote! {
+                    /// Returns the timestamp of the entries recorded in the history of the state
+                    /// machine, for example the time of a monotonic clock.
+                    fn history_timestamp(&self) -> #timestamp_type;
+                },
+            ),
+            None => (quote! { () }, quote! { () }, quote! {}),
+        };
+        let history_type = quote! {
+            ::smlang::history::History<#state_kind_type_name, #event_kind_type_name, #timestamp_type, #size>
+        };
+        (
+            quote! { history: #history_type, },
+            quote! { history: ::smlang::history::History::new(), },
+            quote! {
+                let history_from = self.state.kind();
+                let history_event = event.kind();
+            },
+            quote! {
+                let history_to = self.state.kind();
+                self.history.push(::smlang::history::HistoryEntry {
+                    from: history_from,
+                    event: history_event,
+                    to: history_to,
+                    outcome: match result {
+                        Err(_) => ::smlang::history::Outcome::Failed,
+                        Ok(()) if history_from == history_to => ::smlang::history::Outcome::Internal,
+                        Ok(()) => ::smlang::history::Outcome::Transition,
+                    },
+                    timestamp: #timestamp,
+                });
+            },
+            quote! {
+                /// Returns the last events processed by `process_event`, including failed and
+                /// internal transitions.
+                #[inline(always)]
+                pub fn history(&self) -> &#history_type {
+                    &self.history
+                }
+
+                /// Removes all entries of the history.
+                #[inline(always)]
+                pub fn clear_history(&mut self) {
+                    self.history.clear();
+                }
+            },
+            timestamp_fn,
+        )
+    } else {
+        (
+            quote! {},
+            quote! {},
+            quote! {},
+            quote! {},
+            quote! {},
+            quote! {},
+        )
+    };
+
+    // With `metrics: true`, every call of `process_event` updates counters indexed by transition
+    // and by state
+    let (
+        metrics_field,
+        metrics_init,
+        metrics_start,
+        metrics_record,
+        metrics_methods,
+        metrics_clock,
+    ) = if sm.metrics {
+        let transition_count = sm.transitions().len();
+        let state_count = sm.states.len();
+        let metrics_type = quote! { ::smlang::metrics::Metrics<#transition_count, #state_count> };
+        // With a clock, the time in the starting state is counted from the creation of the state
+        // machine
+        let (record_time, clock_fn, new_metrics, reset) = if sm.metrics_clock {
+            (
+                quote! { self.metrics.record_time(metrics_from.index(), self.context.metrics_clock()); },
+                quote! {
+                    /// Returns the current time of a monotonic clock, in any unit, to measure the
+                    /// time spent in each state.
+                    fn metrics_clock(&self) -> u64;
+                },
+                quote! { ::smlang::metrics::Metrics::started_at(context.metrics_clock()) },
+                quote! { self.metrics = ::smlang::metrics::Metrics::started_at(self.context.metrics_clock()); },
+            )
+        } else {
+            (
+                quote! {},
+                quote! {},
+                quote! { ::smlang::metrics::Metrics::new() },
+                quote! { self.metrics.reset(); },
+            )
+        };
+        let rejected = if sm
+            .all_transitions()
+            .iter()
+            .any(|(_, transition)| transition.reject.is_some())
+        {
+            quote! { | Err(#error_type_name::Rejected(_)) }
+        } else {
+            quote! {}
+        };
+        (
+            quote! { metrics: #metrics_type, },
+            quote! { metrics: #new_metrics, },
+            quote! {
+                let metrics_from = self.state.kind();
+                #record_time
+            },
+            quote! {
+                let metrics_to = self.state.kind();
+                if metrics_to != metrics_from {
+                    self.metrics.record_entry(metrics_to.index());
+                }
+                if matches!(result, Err(#error_type_name::InvalidEvent) | Err(#error_type_name::TransitionsFailed) #rejected) {
+                    self.metrics.record_rejected(metrics_from.index());
+                }
+            },
+            quote! {
+                /// Returns the counters of the transitions, indexed like `TRANSITIONS`, and of
+                /// the states, indexed like `STATE_NAMES`.
+                #[inline(always)]
+                pub fn metrics(&self) -> &#metrics_type {
+                    &self.metrics
+                }
+
+                /// Sets all counters to zero.
+                #[inline(always)]
+                pub fn reset_metrics(&mut self) {
+                    #reset
+                }
+            },
+            clock_fn,
+        )
+    } else {
+        (
+            quote! {},
+            quote! {},
+            quote! {},
+            quote! {},
+            quote! {},
+            quote! {},
+        )
+    };
+
+    // With the `coverage` feature, the transitions taken by all instances are marked in a static
+    let coverage_methods = if cfg!(feature = "coverage") {
+        let transition_count = sm.transitions().len();
+        quote! {
+            fn coverage_marks() -> &'static ::smlang::coverage::Coverage<#transition_count> {
+                static COVERAGE: ::smlang::coverage::Coverage<#transition_count> =
+                    ::smlang::coverage::Coverage::new();
+                &COVERAGE
+            }
+
+            /// Returns the transitions taken and not taken by all instances of the state machine
+            /// since the start of the program or the last `reset_coverage`.
+            pub fn coverage() -> ::smlang::coverage::Report<'static> {
+                Self::coverage_marks().report(&Self::TRANSITIONS)
+            }
+
+            /// Clears the marks of the transitions taken by all instances.
+            pub fn reset_coverage() {
+                Self::coverage_marks().reset();
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // With `replay: true`, the processed events are recorded while recording is started
+    let (replay_field, replay_init, replay_record_event) = if sm.replay {
+        (
+            quote! {
+                event_log: Option<::smlang::replay::EventLog<#events_type_name>>,
+                guard_tape: ::smlang::replay::GuardTape,
+            },
+            quote! {
+                event_log: None,
+                guard_tape: ::smlang::replay::GuardTape::new(),
+            },
+            quote! {
+                if let Some(event_log) = &mut self.event_log {
+                    event_log.record_event(event.clone());
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {})
+    };
+
     let starting_state = &sm.starting_state;
 
     // create a token stream for creating a new machine.  If the starting state contains data, then
     // add a second argument to pass this initial data
     let starting_state_name = starting_state.to_string();
+    // The clock of the metrics can not be read in a `const fn`, and is read before the context is
+    // moved into the state machine, so `metrics_init` comes before `context`
+    let constructor_const = if sm.metrics_clock {
+        quote! {}
+    } else {
+        quote! { const }
+    };
     let new_sm_code = match sm.state_data.data_types.get(&starting_state_name) {
         Some(st) => quote! {
-            pub const fn new(context: T, state_data: #st ) -> Self {
+            pub #constructor_const fn new(context: T, state_data: #st ) -> Self {
                 #state_machine_type_name {
                     state: #states_type_name::#starting_state (state_data),
-                    context
+                    #metrics_init
+                    context,
+                    last_transition: None,
+                    #interrupted_init
+                    #history_init
+                    #replay_init
                 }
             }
         },
         None => quote! {
-            pub const fn new(context: T ) -> Self {
+            pub #constructor_const fn new(context: T ) -> Self {
                 #state_machine_type_name {
                     state: #states_type_name::#starting_state,
-                    context
+                    #metrics_init
+                    context,
+                    last_transition: None,
+                    #interrupted_init
+                    #history_init
+                    #replay_init
                 }
             }
         },
     };
 
-    let state_lifetimes = &sm.state_data.all_lifetimes;
-    let event_lifetimes = &sm.event_data.all_lifetimes;
+    // Static description of the state machine, used for rendering it at runtime
+    let mut state_names: Vec<_> = sm.states.keys().collect();
+    state_names.sort();
+    let state_count = state_names.len();
+    let state_patterns: Vec<_> = state_names
+        .iter()
+        .map(|name| {
+            let state = &sm.states[*name];
+            if sm.state_data.data_types.contains_key(*name) {
+                quote! { #state(..) }
+            } else {
+                quote! { #state }
+            }
+        })
+        .collect();
 
-    // lifetimes that exists in #events_type_name but not in #states_type_name
-    let event_unique_lifetimes = event_lifetimes - state_lifetimes;
+    let table = sm.transitions();
+    let transition_count = table.len();
+    let transition_table: Vec<_> = table
+        .iter()
+        .map(|(event_mapping, transition)| {
+            let from = event_mapping.in_state.to_string();
+            let event = event_mapping.event.to_string();
+            let to = transition.out_state.to_string();
+            let guard = match &transition.guard {
+                Some(guard) => {
+                    let guard = guard.as_written();
+                    quote! { Some(#guard) }
+                }
+                None => quote! { None },
+            };
+            let action = match &transition.action {
+                Some(action) => {
+                    let action = action.ident.to_string();
+                    quote! { Some(#action) }
+                }
+                None => quote! { None },
+            };
+            quote! {
+                ::smlang::TransitionInfo {
+                    from: #from,
+                    event: #event,
+                    guard: #guard,
+                    action: #action,
+                    to: #to,
+                }
+            }
+        })
+        .collect();
+
+    let diagram_name = if sm_name.is_empty() {
+        String::from("G")
+    } else {
+        sm_name.clone()
+    };
+
+    let on_transition = if sm.on_transition {
+        quote! {
+            /// Called for every transition as a result of an event passed to `process_event()`,
+            /// including internal transitions, before the state changes. No-op by default which
+            /// can be overridden in implementations of a state machine's `StateMachineContext`
+            /// trait.
+            #[allow(unused_variables)]
+            fn on_transition(&mut self, transition: &::smlang::TransitionRecord<#states_type_name, #events_type_name>) {}
+        }
+    } else {
+        quote! {}
+    };
 
     let custom_error = if sm.custom_error {
         quote! {
@@ -531,12 +1142,301 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
         quote! {}
     };
 
-    let error_type = if sm.custom_error {
+    // With the `tracing` feature, every call of `process_event` runs inside a span named after the
+    // state machine
+    let tracing = cfg!(feature = "tracing");
+    let (tracing_span, tracing_result) = if tracing {
+        let machine = state_machine_type_name.to_string();
+        (
+            quote! {
+                let span = ::smlang::tracing::debug_span!(#machine, state = %self.state.kind(), event = %event.kind());
+            },
+            quote! {
+                match &result {
+                    Ok(()) => ::smlang::tracing::debug!(parent: &span, state = %self.state.kind(), "transition completed"),
+                    Err(error) => ::smlang::tracing::debug!(parent: &span, ?error, "transition failed"),
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+    let process_event_inner = if !tracing {
+        quote! { self.process_event_inner(#temporary_context_call event) }
+    } else if is_async_state_machine {
+        quote! { ::smlang::tracing::Instrument::instrument(self.process_event_inner(#temporary_context_call event), span.clone()) }
+    } else {
+        quote! { span.in_scope(|| self.process_event_inner(#temporary_context_call event).map(|_| ())) }
+    };
+    let await_inner = if is_async_state_machine {
+        quote! { .await }
+    } else {
+        quote! {}
+    };
+
+    // State machines with interruptible transitions, tracing, a history or metrics wrap
+    // `process_event`, to only keep the transition marked as in progress when the future is
+    // dropped and to trace, record and count the result
+    let (process_event_wrapper, process_event_signature) =
+        if has_interruptible_transitions || tracing || sm.history.is_some() || sm.metrics {
+            (
+                quote! {
+                    /// Process an event.
+                    ///
+                    /// It will return `Ok(&NextState)` if the transition was successful, or `Err(#error_type_name)`
+                    /// if there was an error in the transition.
+                    pub #is_async fn process_event <#event_unique_lifetimes> (
+                        &mut self,
+                        #temporary_context
+                        event: #events_type_name <#event_lifetimes>
+                    ) -> Result<&#states_type_name <#state_lifetimes>, #error_type> {
+                        #interrupted_check
+                        #tracing_span
+                        #history_start
+                        #metrics_start
+                        let result = #process_event_inner #await_inner .map(|_| ());
+                        #interrupted_clear
+                        #tracing_result
+                        #history_record
+                        #metrics_record
+                        result?;
+                        Ok(&self.state)
+                    }
+                },
+                quote! { #is_async fn process_event_inner },
+            )
+        } else {
+            (quote! {}, quote! { pub #is_async fn process_event })
+        };
+
+    let replay_methods = if sm.replay {
         quote! {
-            #error_type_name<<T as #state_machine_context_type_name>::Error>
+            /// Starts recording the processed events and the results of their guards into a new
+            /// event log.
+            #[inline(always)]
+            pub fn start_recording(&mut self) {
+                self.event_log = Some(::smlang::replay::EventLog::new());
+            }
+
+            /// Stops recording and returns the event log, if recording.
+            #[inline(always)]
+            pub fn stop_recording(&mut self) -> Option<::smlang::replay::EventLog<#events_type_name>> {
+                self.event_log.take()
+            }
+
+            /// Returns the event log, if recording.
+            #[inline(always)]
+            pub fn event_log(&self) -> Option<&::smlang::replay::EventLog<#events_type_name>> {
+                self.event_log.as_ref()
+            }
+
+            /// Processes a logged event, using the recorded results of its guards instead of
+            /// calling the guards of the context, which are only called when no recorded results
+            /// are left.
+            pub #is_async fn replay_event(
+                &mut self,
+                #temporary_context
+                logged: &::smlang::replay::LoggedEvent<#events_type_name>
+            ) -> Result<&#states_type_name <#state_lifetimes>, #error_type> {
+                self.guard_tape.load(&logged.guards);
+                let result = self.process_event(#temporary_context_call logged.event.clone()) #await_inner .map(|_| ());
+                self.guard_tape.clear();
+                result?;
+                Ok(&self.state)
+            }
         }
     } else {
-        quote! {#error_type_name}
+        quote! {}
+    };
+
+    // Collect the reasons of the rejected events
+    let mut reject_reasons: Vec<_> = sm
+        .all_transitions()
+        .into_iter()
+        .filter_map(|(_, transition)| transition.reject.as_ref())
+        .collect();
+    reject_reasons.sort_by_key(|reason| reason.to_string());
+    reject_reasons.dedup_by_key(|reason| reason.to_string());
+
+    // The errors of the entry and exit functions and the transition callback only exist for
+    // state machines with `async_hooks`
+    let hook_errors = if sm.async_hooks {
+        quote! {
+            /// When the exit function of the current state returns Err, the state is unchanged.
+            ExitFailed(T),
+            /// When the transition callback returns Err, the state is unchanged.
+            TransitionCallbackFailed(T),
+            /// When the entry function of the new state returns Err, the state has changed.
+            EntryFailed(T),
+        }
+    } else {
+        quote! {}
+    };
+
+    // The reasons and the error variant only exist for state machines which reject events
+    let (reject_reason_enum, rejected_error) = if reject_reasons.is_empty() {
+        (quote! {}, quote! {})
+    } else {
+        (
+            quote! {
+                /// List of reasons for rejecting an event.
+                #[allow(missing_docs)]
+                #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+                pub enum #reject_reason_type_name { #(#reject_reasons),* }
+            },
+            quote! {
+                /// When an event is explicitly rejected in the current state.
+                Rejected(#reject_reason_type_name),
+            },
+        )
+    };
+
+    // Implement the `ProcessEvent` traits, unless processing an event needs more than the event
+    let process_event_impl = if sm.temporary_context_type.is_none()
+        && event_unique_lifetimes.is_empty()
+    {
+        let replay_impl = match (sm.replay, is_async_state_machine) {
+            (false, _) => quote! {},
+            (true, true) => quote! {
+                impl<#state_lifetimes T: #state_machine_context_type_name> ::smlang::replay::ReplayAsync for #state_machine_type_name<#state_lifetimes T> {
+                    async fn replay_event(&mut self, logged: &::smlang::replay::LoggedEvent<Self::Event>) -> Result<(), Self::Error> {
+                        Self::replay_event(self, logged).await.map(|_| ())
+                    }
+                }
+            },
+            (true, false) => quote! {
+                impl<#state_lifetimes T: #state_machine_context_type_name> ::smlang::replay::Replay for #state_machine_type_name<#state_lifetimes T> {
+                    fn replay_event(&mut self, logged: &::smlang::replay::LoggedEvent<Self::Event>) -> Result<(), Self::Error> {
+                        Self::replay_event(self, logged).map(|_| ())
+                    }
+                }
+            },
+        };
+        if is_async_state_machine {
+            // With `async_send`, the future is `Send` whenever the state machine and its events are
+            let send_impl = if sm.async_send {
+                quote! {
+                    impl<#state_lifetimes T: #state_machine_context_type_name> ::smlang::ProcessEventSend for #state_machine_type_name<#state_lifetimes T>
+                    where
+                        Self: Send,
+                        #events_type_name <#event_lifetimes>: Send,
+                    {
+                        type Event = #events_type_name <#event_lifetimes>;
+                        type Error = #error_type;
+
+                        fn process_event(&mut self, event: Self::Event) -> impl core::future::Future<Output = Result<(), Self::Error>> + Send {
+                            async move { Self::process_event(self, event).await.map(|_| ()) }
+                        }
+                    }
+                }
+            } else {
+                quote! {}
+            };
+            quote! {
+                impl<#state_lifetimes T: #state_machine_context_type_name> ::smlang::ProcessEventAsync for #state_machine_type_name<#state_lifetimes T> {
+                    type Event = #events_type_name <#event_lifetimes>;
+                    type Error = #error_type;
+
+                    async fn process_event(&mut self, event: Self::Event) -> Result<(), Self::Error> {
+                        Self::process_event(self, event).await.map(|_| ())
+                    }
+                }
+
+                #send_impl
+
+                #replay_impl
+            }
+        } else {
+            quote! {
+                impl<#state_lifetimes T: #state_machine_context_type_name> ::smlang::ProcessEvent for #state_machine_type_name<#state_lifetimes T> {
+                    type Event = #events_type_name <#event_lifetimes>;
+                    type Error = #error_type;
+
+                    fn process_event(&mut self, event: Self::Event) -> Result<(), Self::Error> {
+                        Self::process_event(self, event).map(|_| ())
+                    }
+                }
+
+                #replay_impl
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let state_kind = generate_kind(
+        &state_kind_type_name,
+        &states_type_name,
+        state_lifetimes,
+        &sm.states,
+        &sm.state_data.data_types,
+    );
+    let event_kind = generate_kind(
+        &event_kind_type_name,
+        &events_type_name,
+        event_lifetimes,
+        &sm.events,
+        &sm.event_data.data_types,
+    );
+
+    // With the `arbitrary` and `proptest` features, events which do not borrow data and the state
+    // kinds can be generated
+    let arbitrary = if event_lifetimes.is_empty() {
+        generate_arbitrary(
+            &events_type_name,
+            &sm.events,
+            &sm.event_data.data_types,
+            &state_kind_type_name,
+            &sm.states,
+        )
+    } else {
+        quote! {}
+    };
+
+    let (log_process_event, log_guard, log_action) =
+        generate_log_functions(&state_machine_type_name.to_string());
+
+    // With `mock: true`, unit tests can use `smlang::mock::Mock` as the context
+    let mock_impl = if sm.mock {
+        let mock_type_name = format_ident!("{sm_name}MockContext", span = sm_name_span);
+        let error = if sm.custom_error {
+            quote! { type Error = (); }
+        } else {
+            quote! {}
+        };
+        let history_timestamp = match &sm.history_timestamp_type {
+            Some(timestamp_type) => quote! {
+                fn history_timestamp(&self) -> #timestamp_type {
+                    Default::default()
+                }
+            },
+            None => quote! {},
+        };
+        let metrics_clock = if sm.metrics_clock {
+            quote! {
+                fn metrics_clock(&self) -> u64 {
+                    0
+                }
+            }
+        } else {
+            quote! {}
+        };
+        quote! {
+            /// A context which records the calls of guards, actions and entry and exit functions,
+            /// for testing the state machine, see `smlang::mock::Mock`.
+            #[cfg(test)]
+            pub type #mock_type_name = ::smlang::mock::Mock;
+
+            #[cfg(test)]
+            impl #state_machine_context_type_name for ::smlang::mock::Mock {
+                #error
+                #mock_functions
+                #history_timestamp
+                #metrics_clock
+            }
+        }
+    } else {
+        quote! {}
     };
 
     let states_attr_list = &sm.states_attr;
@@ -553,24 +1453,39 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
 
 
             /// Called at the beginning of a state machine's `process_event()`. No-op by
-            /// default but can be overridden in implementations of a state machine's
-            /// `StateMachineContext` trait.
-            fn log_process_event(&self, current_state: & #states_type_name, event: & #events_type_name) {}
+            /// default, or logs with the `defmt` and `log` features of `smlang`, but can be
+            /// overridden in implementations of a state machine's `StateMachineContext` trait.
+            #[allow(unused_variables)]
+            fn log_process_event(&self, current_state: & #states_type_name, event: & #events_type_name) {
+                #log_process_event
+            }
 
             /// Called after executing a guard during `process_event()`. No-op by
-            /// default but can be overridden in implementations of a state machine's
-            /// `StateMachineContext` trait.
-            fn log_guard(&self, guard: &'static str, result: bool) {}
+            /// default, or logs with the `defmt` and `log` features of `smlang`, but can be
+            /// overridden in implementations of a state machine's `StateMachineContext` trait.
+            #[allow(unused_variables)]
+            fn log_guard(&self, guard: &'static str, result: bool) {
+                #log_guard
+            }
 
             /// Called after executing an action during `process_event()`. No-op by
-            /// default but can be overridden in implementations of a state machine's
-            /// `StateMachineContext` trait.
-            fn log_action(&self, action: &'static str) {}
+            /// default, or logs with the `defmt` and `log` features of `smlang`, but can be
+            /// overridden in implementations of a state machine's `StateMachineContext` trait.
+            #[allow(unused_variables)]
+            fn log_action(&self, action: &'static str) {
+                #log_action
+            }
 
             /// Called when transitioning to a new state as a result of an event passed to
             /// `process_event()`. No-op by default which can be overridden in implementations
             /// of a state machine's `StateMachineContext` trait.
-            fn transition_callback(&self, old_state: & #states_type_name, new_state: & #states_type_name) {}
+            #hook_async fn transition_callback(&self, old_state: & #states_type_name, new_state: & #states_type_name) #hook_return_type #hook_body
+
+            #on_transition
+
+            #history_timestamp
+
+            #metrics_clock
         }
 
         /// List of auto-generated states.
@@ -586,11 +1501,17 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
             }
         }
 
+        #state_kind
+
         /// List of auto-generated events.
         #[allow(missing_docs)]
         #(#events_attr_list)*
         pub enum #events_type_name <#event_lifetimes> { #(#event_list),* }
 
+        #event_kind
+
+        #arbitrary
+
         /// Manually define PartialEq for #events_type_name based on variant only to address issue-#21
         impl<#event_lifetimes> PartialEq for #events_type_name <#event_lifetimes> {
             fn eq(&self, other: &Self) -> bool {
@@ -599,36 +1520,58 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
             }
         }
 
+        #reject_reason_enum
+
         /// List of possible errors
         #[derive(Debug,PartialEq)]
         pub enum #error_type_name  <T=()> {
             /// When an event is processed which should not come in the current state.
             InvalidEvent,
+            #rejected_error
             /// When an event is processed and none of the transitions happened.
             TransitionsFailed,
             /// When guard is failed.
             GuardFailed(T),
             /// When action returns Err
             ActionFailed(T),
+            #hook_errors
+            #interrupted_error
         }
 
         /// State machine structure definition.
         pub struct #state_machine_type_name<#state_lifetimes T: #state_machine_context_type_name> {
             state: #states_type_name <#state_lifetimes>,
-            context: T
+            context: T,
+            last_transition: Option<usize>,
+            #interrupted_field
+            #history_field
+            #metrics_field
+            #replay_field
         }
 
         impl<#state_lifetimes T: #state_machine_context_type_name> #state_machine_type_name<#state_lifetimes T> {
+            /// Names of all states.
+            pub const STATE_NAMES: [&'static str; #state_count] = [#(#state_names),*];
+
+            /// All transitions of the state machine in the order they are defined, after expanding
+            /// wildcards and input state patterns.
+            pub const TRANSITIONS: [::smlang::TransitionInfo; #transition_count] = [#(#transition_table),*];
+
             /// Creates a new state machine with the specified starting state.
             #[inline(always)]
             #new_sm_code
 
             /// Creates a new state machine with an initial state.
             #[inline(always)]
-            pub const fn new_with_state(context: T, initial_state: #states_type_name <#state_lifetimes>) -> Self {
+            pub #constructor_const fn new_with_state(context: T, initial_state: #states_type_name <#state_lifetimes>) -> Self {
                 #state_machine_type_name {
                     state: initial_state,
-                    context
+                    #metrics_init
+                    context,
+                    last_transition: None,
+                    #interrupted_init
+                    #history_init
+                    #replay_init
                 }
             }
 
@@ -638,6 +1581,22 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
                 &self.state
             }
 
+            /// Returns the last transition taken, if any.
+            #[inline(always)]
+            pub fn last_transition(&self) -> Option<&'static ::smlang::TransitionInfo> {
+                self.last_transition.map(|index| &Self::TRANSITIONS[index])
+            }
+
+            #interrupted_methods
+
+            #history_methods
+
+            #metrics_methods
+
+            #replay_methods
+
+            #coverage_methods
+
             /// Returns the current context.
             #[inline(always)]
             pub fn context(&self) -> &T {
@@ -650,28 +1609,47 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
                 &mut self.context
             }
 
+            /// Writes the state machine as a graphviz dot diagram, with the current state
+            /// highlighted and the last transition taken emphasised.
+            pub fn write_dot(&self, w: &mut impl core::fmt::Write) -> core::fmt::Result {
+                let current = match self.state {
+                    #(#states_type_name::#state_patterns => #state_names),*
+                };
+                ::smlang::dot::Diagram {
+                    name: #diagram_name,
+                    states: &Self::STATE_NAMES,
+                    starting_state: #starting_state_name,
+                    transitions: &Self::TRANSITIONS,
+                }
+                .write(w, current, self.last_transition)
+            }
+
+            #process_event_wrapper
+
             /// Process an event.
             ///
             /// It will return `Ok(&NextState)` if the transition was successful, or `Err(#error_type_name)`
             /// if there was an error in the transition.
-            pub #is_async fn process_event <#event_unique_lifetimes> (
+            #process_event_signature <#event_unique_lifetimes> (
                 &mut self,
                 #temporary_context
                 event: #events_type_name <#event_lifetimes>
             ) -> Result<&#states_type_name <#state_lifetimes>, #error_type> {
                 self.context.log_process_event(self.state(), &event);
+                #replay_record_event
                match self.state {
                     #(
                     #[allow(clippy::match_single_binding)]
                     #states_type_name::#in_states => match event {
-                        #(#events_type_name::#events => {
+                        #(
+                        // Transitions following an unguarded transition are unreachable, which
+                        // is reported by the `unreachable_transition` lint instead
+                        #[allow(unreachable_code)]
+                        #events_type_name::#events => {
                             #code_blocks
 
-                            #[allow(unreachable_code)]
-                            {
-                                // none of the guarded or non-guarded transitions occurred,
-                                Err(#error_type_name ::TransitionsFailed)
-                            }
+                            // none of the guarded or non-guarded transitions occurred,
+                            Err(#error_type_name ::TransitionsFailed)
                         }),*
                         #[allow(unreachable_patterns)]
                         _ => Err(#error_type_name ::InvalidEvent),
@@ -679,8 +1657,41 @@ pub fn generate_code(sm: &ParsedStateMachine) -> proc_macro2::TokenStream {
                 }
             }
         }
+
+        #process_event_impl
+
+        #mock_impl
+    }
+}
+
+// Generates the body of a function of the mock context returning `value`, which returns a future
+// when the function is async with `async_send`.
+fn mock_body(returns_future: bool, value: TokenStream) -> TokenStream {
+    if returns_future {
+        quote! { { let value = { #value }; async move { value } } }
+    } else {
+        quote! { { #value } }
     }
 }
+
+// Returns the `async` keyword and the return type of a guard or action. With `async_send`, async
+// functions return a `Send` future instead, so the state machine can be used on multi-threaded
+// executors.
+fn async_signature(
+    is_async: bool,
+    async_send: bool,
+    return_type: TokenStream,
+) -> (TokenStream, TokenStream) {
+    match (is_async, async_send) {
+        (false, _) => (quote! {}, return_type),
+        (true, false) => (quote! { async }, return_type),
+        (true, true) => (
+            quote! {},
+            quote! { impl core::future::Future<Output = #return_type> + Send },
+        ),
+    }
+}
+
 fn generate_action(
     action: &Option<AsyncIdent>,
     temporary_context_call: &TokenStream,
@@ -699,13 +1710,253 @@ fn generate_action(
         } else {
             quote! {}
         };
+        let trace_action = if cfg!(feature = "tracing") {
+            quote! { ::smlang::tracing::debug!(action = stringify!(#action_ident), "action"); }
+        } else {
+            quote! {}
+        };
         quote! {
             // ACTION
             let _data = self.context.#action_ident(#temporary_context_call #g_a_param) #action_await .map_err(#error_type_name::ActionFailed)?;
             self.context.log_action(stringify!(#action_ident));
+            #trace_action
         }
     } else {
         quote! {}
     };
     (is_async, code)
 }
+
+// Generates the fieldless enum `kind_type_name` with the variants of `type_name`, and the `kind`
+// method returning the variant of a value. With the `defmt` feature, the kind implements
+// `defmt::Format`, without requiring it for the data of the variants.
+fn generate_kind(
+    kind_type_name: &Ident,
+    type_name: &Ident,
+    lifetimes: &Lifetimes,
+    variants: &HashMap<String, Ident>,
+    data_types: &HashMap<String, Type>,
+) -> TokenStream {
+    let mut names: Vec<_> = variants.keys().collect();
+    names.sort();
+    let idents: Vec<_> = names.iter().map(|name| &variants[*name]).collect();
+    let patterns: Vec<_> = names
+        .iter()
+        .map(|name| {
+            let ident = &variants[*name];
+            if data_types.contains_key(*name) {
+                quote! { #ident(..) }
+            } else {
+                quote! { #ident }
+            }
+        })
+        .collect();
+
+    let defmt_format = if cfg!(feature = "defmt") {
+        quote! {
+            impl ::smlang::defmt::Format for #kind_type_name {
+                fn format(&self, f: ::smlang::defmt::Formatter<'_>) {
+                    ::smlang::defmt::Format::format(self.name(), f)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let doc = format!("The variants of [`{}`], without their data.", type_name);
+    quote! {
+        #[doc = #doc]
+        #[allow(missing_docs, non_camel_case_types)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum #kind_type_name { #(#idents),* }
+
+        impl #kind_type_name {
+            /// Returns the name of the variant.
+            pub const fn name(self) -> &'static str {
+                match self {
+                    #(#kind_type_name::#idents => #names),*
+                }
+            }
+
+            /// Returns the index of the variant in the alphabetical order of the variants, which
+            /// is the order of `STATE_NAMES` for states.
+            pub const fn index(self) -> usize {
+                self as usize
+            }
+        }
+
+        impl core::fmt::Display for #kind_type_name {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+
+        #defmt_format
+
+        impl<#lifetimes> #type_name <#lifetimes> {
+            /// Returns the variant, without its data.
+            pub const fn kind(&self) -> #kind_type_name {
+                match self {
+                    #(#type_name::#patterns => #kind_type_name::#idents),*
+                }
+            }
+        }
+    }
+}
+
+// Generates the implementations of `Arbitrary` of the `arbitrary` and `proptest` crates for the
+// events and the state kinds, when the corresponding feature is enabled.
+fn generate_arbitrary(
+    events_type_name: &Ident,
+    events: &HashMap<String, Ident>,
+    event_data_types: &HashMap<String, Type>,
+    state_kind_type_name: &Ident,
+    states: &HashMap<String, Ident>,
+) -> TokenStream {
+    let mut event_names: Vec<_> = events.keys().collect();
+    event_names.sort();
+    let mut state_names: Vec<_> = states.keys().collect();
+    state_names.sort();
+    let state_kinds: Vec<_> = state_names.iter().map(|name| &states[*name]).collect();
+
+    // The implementations only apply when the data of the events implements the traits, and
+    // `Debug` for `proptest`, so they do not prevent compiling other state machines. The
+    // bounds are not trivial as they use a lifetime.
+    let data_types: Vec<_> = event_names
+        .iter()
+        .filter_map(|name| event_data_types.get(*name))
+        .collect();
+
+    let mut code = TokenStream::new();
+    if cfg!(feature = "arbitrary") {
+        let event_count = event_names.len();
+        let constructors = event_names.iter().enumerate().map(|(index, name)| {
+            let event = &events[*name];
+            if event_data_types.contains_key(*name) {
+                quote! { #index => #events_type_name::#event(::smlang::arbitrary::Arbitrary::arbitrary(u)?) }
+            } else {
+                quote! { #index => #events_type_name::#event }
+            }
+        });
+        code.extend(quote! {
+            impl<'arbitrary> ::smlang::arbitrary::Arbitrary<'arbitrary> for #events_type_name
+            where
+                #(#data_types: ::smlang::arbitrary::Arbitrary<'arbitrary>,)*
+            {
+                fn arbitrary(u: &mut ::smlang::arbitrary::Unstructured<'arbitrary>) -> ::smlang::arbitrary::Result<Self> {
+                    Ok(match u.choose_index(#event_count)? {
+                        #(#constructors,)*
+                        _ => unreachable!(),
+                    })
+                }
+            }
+
+            impl<'arbitrary> ::smlang::arbitrary::Arbitrary<'arbitrary> for #state_kind_type_name {
+                fn arbitrary(u: &mut ::smlang::arbitrary::Unstructured<'arbitrary>) -> ::smlang::arbitrary::Result<Self> {
+                    Ok(*u.choose(&[#(#state_kind_type_name::#state_kinds),*])?)
+                }
+            }
+        });
+    }
+    if cfg!(feature = "proptest") {
+        let strategies = event_names.iter().map(|name| {
+            let event = &events[*name];
+            match event_data_types.get(*name) {
+                Some(data_type) => quote! {
+                    ::smlang::proptest::arbitrary::any::<#data_type>().prop_map(#events_type_name::#event).boxed()
+                },
+                None => quote! {
+                    ::smlang::proptest::strategy::LazyJust::new(|| #events_type_name::#event).boxed()
+                },
+            }
+        });
+        code.extend(quote! {
+            impl ::smlang::proptest::arbitrary::Arbitrary for #events_type_name
+            where
+                for<'proptest> #events_type_name: core::fmt::Debug,
+                #(for<'proptest> #data_types: ::smlang::proptest::arbitrary::Arbitrary,)*
+            {
+                type Parameters = ();
+                type Strategy = ::smlang::proptest::strategy::BoxedStrategy<Self>;
+
+                fn arbitrary_with(_: ()) -> Self::Strategy {
+                    use ::smlang::proptest::strategy::Strategy;
+                    ::smlang::proptest::strategy::Union::new([#(#strategies),*]).boxed()
+                }
+            }
+
+            impl ::smlang::proptest::arbitrary::Arbitrary for #state_kind_type_name {
+                type Parameters = ();
+                type Strategy = ::smlang::proptest::sample::Select<Self>;
+
+                fn arbitrary_with(_: ()) -> Self::Strategy {
+                    ::smlang::proptest::sample::select(&[#(#state_kind_type_name::#state_kinds),*][..])
+                }
+            }
+        });
+    }
+    code
+}
+
+// Generates the default bodies of `log_process_event`, `log_guard` and `log_action`, which log
+// through `defmt` or `log` when the corresponding feature is enabled.
+fn generate_log_functions(machine: &str) -> (TokenStream, TokenStream, TokenStream) {
+    let mut process_event = TokenStream::new();
+    let mut guard = TokenStream::new();
+    let mut action = TokenStream::new();
+
+    if cfg!(feature = "defmt") {
+        // The `defmt` macros refer to the `defmt` crate, which is re-exported by `smlang`
+        process_event.extend(quote! {{
+            use ::smlang::defmt;
+            defmt::debug!("{=str}: processing {} in state {}", #machine, event.kind(), current_state.kind());
+        }});
+        guard.extend(quote! {{
+            use ::smlang::defmt;
+            defmt::debug!("{=str}: guard {=str} {=str}", #machine, guard, if result { "passed" } else { "failed" });
+        }});
+        action.extend(quote! {{
+            use ::smlang::defmt;
+            defmt::debug!("{=str}: action {=str}", #machine, action);
+        }});
+    }
+
+    if cfg!(feature = "log") {
+        process_event.extend(quote! {
+            ::smlang::log::debug!("{}: processing {} in state {}", #machine, event.kind(), current_state.kind());
+        });
+        guard.extend(quote! {
+            ::smlang::log::debug!("{}: guard {} {}", #machine, guard, if result { "passed" } else { "failed" });
+        });
+        action.extend(quote! {
+            ::smlang::log::debug!("{}: action {}", #machine, action);
+        });
+    }
+
+    (process_event, guard, action)
+}
+
+// Emits the findings of lints configured to `warn` as compiler warnings. There is no stable way
+// for a proc-macro to emit a warning, so each one is the use of a deprecated constant, named after
+// the lint and spanned to the offending part of the state machine definition.
+pub fn generate_warnings(warnings: &crate::validation::Findings) -> TokenStream {
+    warnings
+        .iter()
+        .flat_map(|(lint, warning)| {
+            warning.clone().into_iter().map(move |part| {
+                let span = part.span();
+                let name = Ident::new(lint.name(), span);
+                let message = part.to_string();
+                quote_spanned! {span=>
+                    const _: () = {
+                        #[deprecated(note = #message)]
+                        #[allow(non_upper_case_globals)]
+                        const #name: () = ();
+                        #name
+                    };
+                }
+            })
+        })
+        .collect()
+}
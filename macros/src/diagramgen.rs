@@ -1,4 +1,5 @@
 use crate::parser::*;
+use std::hash::{Hash, Hasher};
 
 /// Generates a string containing 'dot' syntax to generate a statemachine diagram with graphviz.
 pub fn generate_diagram(sm: &ParsedStateMachine) -> String {
@@ -11,7 +12,11 @@ pub fn generate_diagram(sm: &ParsedStateMachine) -> String {
     let mut diagram_transitions = vec![];
     for (state, event) in transitions {
         for eventmapping in event.values() {
-            for transition in &eventmapping.transitions {
+            for transition in eventmapping
+                .transitions
+                .iter()
+                .filter(|t| t.is_transition())
+            {
                 diagram_events.push((
                     eventmapping.event.to_string(),
                     transition
@@ -83,3 +88,15 @@ pub fn generate_diagram(sm: &ParsedStateMachine) -> String {
         transition_string.join("\n")
     )
 }
+
+/// Returns the name used for the rendered diagram of a statemachine. Unnamed statemachines are
+/// named after a hash of their diagram, which is stable as long as the structure does not change.
+pub fn diagram_name(sm: &ParsedStateMachine, diagram: &str) -> String {
+    if let Some(name) = &sm.name {
+        name.to_string()
+    } else {
+        let mut diagram_hasher = std::collections::hash_map::DefaultHasher::new();
+        diagram.hash(&mut diagram_hasher);
+        format!("smlang{:010x}", diagram_hasher.finish())
+    }
+}
@@ -23,18 +23,11 @@ pub fn statemachine(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         Ok(sm) => {
             #[cfg(feature = "graphviz")]
             {
-                use std::hash::{Hash, Hasher};
                 use std::io::Write;
 
                 // Generate dot syntax for the statemachine.
                 let diagram = diagramgen::generate_diagram(&sm);
-                let diagram_name = if let Some(name) = &sm.name {
-                    name.to_string()
-                } else {
-                    let mut diagram_hasher = std::collections::hash_map::DefaultHasher::new();
-                    diagram.hash(&mut diagram_hasher);
-                    format!("smlang{:010x}", diagram_hasher.finish())
-                };
+                let diagram_name = diagramgen::diagram_name(&sm, &diagram);
 
                 // Start the 'dot' process.
                 let mut process = std::process::Command::new("dot")
@@ -61,11 +54,17 @@ pub fn statemachine(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             }
 
             // Validate the parsed state machine before generating code.
-            if let Err(e) = validation::validate(&sm) {
-                return e.to_compile_error().into();
-            }
+            let warnings = match validation::validate(&sm) {
+                Ok(warnings) => codegen::generate_warnings(&warnings),
+                Err(e) => return e.to_compile_error().into(),
+            };
 
-            codegen::generate_code(&sm).into()
+            let code = codegen::generate_code(&sm);
+            quote::quote! {
+                #warnings
+                #code
+            }
+            .into()
         }
         Err(error) => error.to_compile_error().into(),
     }
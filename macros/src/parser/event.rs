@@ -1,5 +1,6 @@
 use crate::parser::transition::GuardExpression;
 use crate::parser::AsyncIdent;
+use proc_macro2::Span;
 use syn::{parenthesized, parse, spanned::Spanned, token, Ident, Token, Type};
 
 #[derive(Debug, Clone)]
@@ -20,6 +21,24 @@ pub struct Transition {
     pub guard: Option<GuardExpression>,
     pub action: Option<AsyncIdent>,
     pub out_state: Ident,
+    // Position of this transition in the generated transition table.
+    pub index: usize,
+    // Position of this transition in the state machine definition, transitions expanded from the
+    // same wildcard or pattern follow each other.
+    pub position: usize,
+    // The input state as written in the definition, i.e. the `_` of a wildcard transition.
+    pub span: Span,
+    // The event is explicitly ignored, so this is not an actual transition.
+    pub ignore: bool,
+    // The event is explicitly rejected with this reason, so this is not an actual transition.
+    pub reject: Option<Ident>,
+}
+
+impl Transition {
+    /// Returns false for ignored and rejected events, which do not change the state.
+    pub fn is_transition(&self) -> bool {
+        !self.ignore && self.reject.is_none()
+    }
 }
 
 impl parse::Parse for Event {
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use syn::{braced, parse, Ident, Token};
+
+/// A check of the state machine whose level can be configured with `lints: { ... }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnreachableTransition,
+    ContradictoryGuard,
+    UnreachableState,
+    DeadEnd,
+    NonExhaustive,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 5] = [
+        Lint::UnreachableTransition,
+        Lint::ContradictoryGuard,
+        Lint::UnreachableState,
+        Lint::DeadEnd,
+        Lint::NonExhaustive,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnreachableTransition => "unreachable_transition",
+            Lint::ContradictoryGuard => "contradictory_guard",
+            Lint::UnreachableState => "unreachable_state",
+            Lint::DeadEnd => "dead_end",
+            Lint::NonExhaustive => "non_exhaustive",
+        }
+    }
+
+    // The level of the lint when it is not configured.
+    fn default_level(self) -> Level {
+        match self {
+            Lint::NonExhaustive => Level::Allow,
+            _ => Level::Deny,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+#[derive(Debug, Default)]
+pub struct Lints {
+    levels: HashMap<Lint, Level>,
+}
+
+impl Lints {
+    pub fn level(&self, lint: Lint) -> Level {
+        self.levels
+            .get(&lint)
+            .copied()
+            .unwrap_or_else(|| lint.default_level())
+    }
+
+    // Changes the level of a lint, unless it has been configured explicitly.
+    pub fn set_default(&mut self, lint: Lint, level: Level) {
+        self.levels.entry(lint).or_insert(level);
+    }
+}
+
+impl parse::Parse for Lints {
+    fn parse(input: parse::ParseStream) -> syn::Result<Self> {
+        let content;
+        braced!(content in input);
+
+        let mut levels = HashMap::new();
+        while !content.is_empty() {
+            let name: Ident = content.parse()?;
+            let lint = Lint::ALL
+                .iter()
+                .copied()
+                .find(|lint| name == lint.name())
+                .ok_or_else(|| {
+                    let names: Vec<_> = Lint::ALL.iter().map(|lint| lint.name()).collect();
+                    parse::Error::new(
+                        name.span(),
+                        format!("Unknown lint `{}`. Supported lints: {:?}", name, names),
+                    )
+                })?;
+
+            content.parse::<Token![:]>()?;
+            let level: Ident = content.parse()?;
+            let level = match level.to_string().as_str() {
+                "allow" => Level::Allow,
+                "warn" => Level::Warn,
+                "deny" => Level::Deny,
+                _ => {
+                    return Err(parse::Error::new(
+                        level.span(),
+                        format!(
+                            "Unknown lint level `{}`, expected `allow`, `warn` or `deny`.",
+                            level
+                        ),
+                    ))
+                }
+            };
+
+            if levels.insert(lint, level).is_some() {
+                return Err(parse::Error::new(
+                    name.span(),
+                    format!("Lint `{}` is configured multiple times.", name),
+                ));
+            }
+
+            if content.parse::<Token![,]>().is_err() {
+                break;
+            }
+        }
+
+        if !content.is_empty() {
+            return Err(content.error("expected `,`"));
+        }
+
+        Ok(Lints { levels })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Level, Lint, Lints};
+    use syn::parse_str;
+
+    #[test]
+    fn lint_levels() -> Result<(), syn::Error> {
+        let mut lints: Lints = parse_str("{ unreachable_state: warn, dead_end: allow, }")?;
+        lints.set_default(Lint::DeadEnd, Level::Deny);
+        lints.set_default(Lint::NonExhaustive, Level::Deny);
+
+        assert_eq!(lints.level(Lint::UnreachableState), Level::Warn);
+        assert_eq!(lints.level(Lint::DeadEnd), Level::Allow);
+        assert_eq!(lints.level(Lint::NonExhaustive), Level::Deny);
+        assert_eq!(lints.level(Lint::ContradictoryGuard), Level::Deny);
+        Ok(())
+    }
+
+    #[test]
+    fn bad_lints() {
+        assert!(parse_str::<Lints>("{ unknown_lint: warn }").is_err());
+        assert!(parse_str::<Lints>("{ dead_end: forbid }").is_err());
+        assert!(parse_str::<Lints>("{ dead_end: warn, dead_end: deny }").is_err());
+    }
+}
@@ -2,12 +2,14 @@ pub mod data;
 pub mod event;
 pub mod input_state;
 pub mod lifetimes;
+pub mod lints;
 pub mod output_state;
 pub mod state_machine;
 pub mod transition;
 
 use data::DataDefinitions;
 use event::EventMapping;
+use lints::{Level, Lint, Lints};
 use state_machine::StateMachine;
 
 use input_state::InputState;
@@ -50,8 +52,22 @@ pub struct ParsedStateMachine {
     pub events_attr: Vec<Attribute>,
     pub temporary_context_type: Option<Type>,
     pub custom_error: bool,
+    pub async_send: bool,
+    pub async_hooks: bool,
+    pub entry_exit_event: bool,
+    pub on_transition: bool,
+    pub history: Option<usize>,
+    pub history_timestamp_type: Option<Type>,
+    pub metrics: bool,
+    pub metrics_clock: bool,
+    pub replay: bool,
+    pub replay_span: Option<Span>,
+    pub mock: bool,
     pub states: HashMap<String, Ident>,
     pub starting_state: Ident,
+    pub entry_states: Vec<Ident>,
+    pub terminal_states: Vec<Ident>,
+    pub lints: Lints,
     pub state_data: DataDefinitions,
     pub events: HashMap<String, Ident>,
     pub event_data: DataDefinitions,
@@ -61,6 +77,8 @@ pub struct ParsedStateMachine {
 // helper function for adding a transition to a transition event map
 fn add_transition(
     transition: &StateTransition,
+    span: Span,
+    position: usize,
     transition_map: &mut TransitionMap,
     state_data: &DataDefinitions,
 ) -> Result<(), parse::Error> {
@@ -68,40 +86,52 @@ fn add_transition(
         .get_mut(&transition.in_state.ident.to_string())
         .unwrap();
 
+    let mapped_transition = Transition {
+        guard: transition.guard.clone(),
+        action: transition.action.clone(),
+        out_state: transition.out_state.ident.clone(),
+        index: 0,
+        position,
+        span,
+        ignore: transition.out_state.ignore,
+        reject: transition.out_state.reject.clone(),
+    };
+
     match p.entry(transition.event.ident.to_string()) {
         hash_map::Entry::Vacant(entry) => {
             let mapping = EventMapping {
                 in_state: transition.in_state.ident.clone(),
                 event: transition.event.ident.clone(),
-                transitions: vec![Transition {
-                    guard: transition.guard.clone(),
-                    action: transition.action.clone(),
-                    out_state: transition.out_state.ident.clone(),
-                }],
+                transitions: vec![mapped_transition],
             };
             entry.insert(mapping);
         }
         hash_map::Entry::Occupied(mut entry) => {
             let mapping = entry.get_mut();
-            mapping.transitions.push(Transition {
-                guard: transition.guard.clone(),
-                action: transition.action.clone(),
-                out_state: transition.out_state.ident.clone(),
-            });
+            mapping.transitions.push(mapped_transition);
         }
     }
 
     // Check for actions when states have data a
-    if state_data
-        .data_types
-        .contains_key(&transition.out_state.ident.to_string())
+    if !transition.out_state.ignore
+        && transition.out_state.reject.is_none()
+        && state_data
+            .data_types
+            .contains_key(&transition.out_state.ident.to_string())
     {
         // This transition goes to a state that has data associated, check so it has an
         // action
 
         if transition.action.is_none() {
+            // The output state of an internal wildcard transition is not written anywhere, so
+            // point at the wildcard instead
+            let span = if transition.out_state.internal_transition {
+                span
+            } else {
+                transition.out_state.ident.span()
+            };
             return Err(parse::Error::new(
-                transition.out_state.ident.span(),
+                span,
                 "This state has data associated, but not action is define here to provide it.",
             ));
         }
@@ -131,11 +161,16 @@ impl ParsedStateMachine {
             "No starting state defined, indicate the starting state with a *.",
         ))?;
 
-        if starting_transitions_iter.next().is_some() {
-            return Err(parse::Error::new(
-                Span::call_site(),
+        if let Some(duplicate) = starting_transitions_iter.next() {
+            let mut error = parse::Error::new(
+                duplicate.in_state.ident.span(),
                 "More than one starting state defined (indicated with *), remove duplicates.",
+            );
+            error.combine(parse::Error::new(
+                starting_transition.in_state.ident.span(),
+                "The starting state is first defined here.",
             ));
+            return Err(error);
         }
 
         // Extract the starting state
@@ -148,15 +183,19 @@ impl ParsedStateMachine {
         let mut states_events_mapping = TransitionMap::new();
 
         for transition in sm.transitions.iter() {
-            // Collect states
+            // Collect states, errors about a state point at its first occurrence
             let in_state_name = transition.in_state.ident.to_string();
             if !transition.in_state.wildcard {
-                states.insert(in_state_name.clone(), transition.in_state.ident.clone());
+                states
+                    .entry(in_state_name.clone())
+                    .or_insert_with(|| transition.in_state.ident.clone());
                 state_data.collect(in_state_name.clone(), transition.in_state.data_type.clone())?;
             }
             if !transition.out_state.internal_transition {
                 let out_state_name = transition.out_state.ident.to_string();
-                states.insert(out_state_name.clone(), transition.out_state.ident.clone());
+                states
+                    .entry(out_state_name.clone())
+                    .or_insert_with(|| transition.out_state.ident.clone());
                 state_data.collect(
                     out_state_name.clone(),
                     transition.out_state.data_type.clone(),
@@ -178,12 +217,21 @@ impl ParsedStateMachine {
             }
         }
 
+        // Wildcards are expanded in order of the state names, so the positions are stable
+        let mut sorted_states: Vec<_> = states.iter().collect();
+        sorted_states.sort_by_key(|(name, _)| *name);
+
+        let mut position = 0;
         for transition in sm.transitions.iter() {
+            // Errors point at the input state as written, i.e. at the wildcard or at the state
+            // within a pattern
+            let span = transition.in_state.ident.span();
+
             // if input state is a wildcard, we need to add this transition for all states
             if transition.in_state.wildcard {
                 let mut transition_added = false;
 
-                for (name, in_state) in &states {
+                for (name, in_state) in &sorted_states {
                     // skip already set input state
                     let p = states_events_mapping
                         .get_mut(&in_state.to_string())
@@ -197,8 +245,8 @@ impl ParsedStateMachine {
                     let in_state = InputState {
                         start: false,
                         wildcard: false,
-                        ident: in_state.clone(),
-                        data_type: state_data.data_types.get(name).cloned(),
+                        ident: (*in_state).clone(),
+                        data_type: state_data.data_types.get(*name).cloned(),
                     };
 
                     // create the transition
@@ -216,14 +264,15 @@ impl ParsedStateMachine {
                     };
 
                     // add the wildcard transition to the transition map
-                    // TODO:  Need to work on the span of this error, as it is being caused by the wildcard
-                    // but won't show up at that line
                     add_transition(
                         &wildcard_transition,
+                        span,
+                        position,
                         &mut states_events_mapping,
                         &state_data,
                     )?;
 
+                    position += 1;
                     transition_added = true;
                 }
 
@@ -236,22 +285,95 @@ impl ParsedStateMachine {
                     ));
                 }
             } else {
-                add_transition(transition, &mut states_events_mapping, &state_data)?;
+                add_transition(
+                    transition,
+                    span,
+                    position,
+                    &mut states_events_mapping,
+                    &state_data,
+                )?;
+                position += 1;
             }
         }
 
+        // The transition table follows the order of the definition, ignored and rejected events
+        // are not part of it
+        let mut table: Vec<_> = states_events_mapping
+            .values_mut()
+            .flat_map(|event_mappings| event_mappings.values_mut())
+            .flat_map(|event_mapping| event_mapping.transitions.iter_mut())
+            .filter(|transition| transition.is_transition())
+            .collect();
+        table.sort_by_key(|transition| transition.position);
+        for (index, transition) in table.into_iter().enumerate() {
+            transition.index = index;
+        }
+
+        // `exhaustive: true` is a shorthand for denying non exhaustive state machines
+        let mut lints = sm.lints;
+        if sm.exhaustive {
+            lints.set_default(Lint::NonExhaustive, Level::Deny);
+        }
+
         Ok(ParsedStateMachine {
             name: sm.name,
             states_attr: sm.states_attr,
             events_attr: sm.events_attr,
             temporary_context_type: sm.temporary_context_type,
             custom_error: sm.custom_error,
+            async_send: sm.async_send,
+            async_hooks: sm.async_hooks,
+            entry_exit_event: sm.entry_exit_event,
+            on_transition: sm.on_transition,
+            history: sm.history,
+            history_timestamp_type: sm.history_timestamp_type,
+            metrics: sm.metrics,
+            metrics_clock: sm.metrics_clock,
+            replay: sm.replay,
+            replay_span: sm.replay_span,
+            mock: sm.mock,
             states,
             starting_state,
+            entry_states: sm.entry_states,
+            terminal_states: sm.terminal_states,
+            lints,
             state_data,
             events,
             event_data,
             states_events_mapping,
         })
     }
+
+    /// Returns true if the event is borrowed after the action is called, by the entry function
+    /// or `on_transition`, so the event data can not be moved into the action.
+    pub fn borrows_event(&self) -> bool {
+        self.entry_exit_event || self.on_transition
+    }
+
+    /// Returns all transitions with the event mapping they belong to, including ignored and
+    /// rejected events, in the order they are defined.
+    pub fn all_transitions(&self) -> Vec<(&EventMapping, &Transition)> {
+        let mut transitions: Vec<_> = self
+            .states_events_mapping
+            .values()
+            .flat_map(|event_mappings| event_mappings.values())
+            .flat_map(|event_mapping| {
+                event_mapping
+                    .transitions
+                    .iter()
+                    .map(move |transition| (event_mapping, transition))
+            })
+            .collect();
+        transitions.sort_by_key(|(_, transition)| transition.position);
+        transitions
+    }
+
+    /// Returns all transitions with the event mapping they belong to, in transition table order.
+    /// Ignored and rejected events are not part of the transition table.
+    pub fn transitions(&self) -> Vec<(&EventMapping, &Transition)> {
+        self.all_transitions()
+            .into_iter()
+            .filter(|(_, transition)| transition.is_transition())
+            .collect()
+    }
 }
@@ -6,12 +6,50 @@ pub struct OutputState {
     pub ident: Ident,
     pub internal_transition: bool,
     pub data_type: Option<Type>,
+    // The event is explicitly ignored, denoted with `= ignore`
+    pub ignore: bool,
+    // The event is explicitly rejected with a reason, denoted with `= reject(Reason)`
+    pub reject: Option<Ident>,
 }
 
 impl parse::Parse for OutputState {
     fn parse(input: parse::ParseStream) -> syn::Result<Self> {
         if input.peek(Token![=]) {
             input.parse::<Token![=]>()?;
+
+            // `ignore` is used to explicitly accept an event without doing anything
+            if input
+                .fork()
+                .parse::<Ident>()
+                .is_ok_and(|ident| ident == "ignore")
+            {
+                let ident: Ident = input.parse()?;
+                return Ok(Self {
+                    ident: Ident::new("_", ident.span()),
+                    internal_transition: true,
+                    data_type: None,
+                    ignore: true,
+                    reject: None,
+                });
+            }
+
+            // `reject(Reason)` is used to explicitly refuse an event with a reason
+            let fork = input.fork();
+            if fork.parse::<Ident>().is_ok_and(|ident| ident == "reject") && fork.peek(token::Paren)
+            {
+                let ident: Ident = input.parse()?;
+                let content;
+                parenthesized!(content in input);
+                let reason: Ident = content.parse()?;
+                return Ok(Self {
+                    ident: Ident::new("_", ident.span()),
+                    internal_transition: true,
+                    data_type: None,
+                    ignore: false,
+                    reject: Some(reason),
+                });
+            }
+
             let (internal_transition, ident) = if input.peek(Token![_]) {
                 // Underscore ident here is used to represent an internal transition
                 let underscore = input.parse::<Token![_]>()?;
@@ -51,6 +89,8 @@ impl parse::Parse for OutputState {
                 ident,
                 internal_transition,
                 data_type,
+                ignore: false,
+                reject: None,
             })
         } else {
             // Internal transition
@@ -58,6 +98,8 @@ impl parse::Parse for OutputState {
                 ident: Ident::new("_", Span::call_site()),
                 internal_transition: true,
                 data_type: None,
+                ignore: false,
+                reject: None,
             })
         }
     }
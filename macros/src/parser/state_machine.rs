@@ -1,14 +1,33 @@
+use super::lints::Lints;
 use super::transition::{StateTransition, StateTransitions};
-use syn::{braced, parse, spanned::Spanned, token, Attribute, Ident, Token, Type};
+use syn::{
+    braced, bracketed, parse, punctuated::Punctuated, spanned::Spanned, token, Attribute, Ident,
+    Token, Type,
+};
 
 #[derive(Debug)]
 pub struct StateMachine {
     pub temporary_context_type: Option<Type>,
     pub custom_error: bool,
+    pub async_send: bool,
+    pub async_hooks: bool,
+    pub entry_exit_event: bool,
+    pub on_transition: bool,
+    pub history: Option<usize>,
+    pub history_timestamp_type: Option<Type>,
+    pub metrics: bool,
+    pub metrics_clock: bool,
+    pub replay: bool,
+    pub replay_span: Option<proc_macro2::Span>,
+    pub mock: bool,
+    pub exhaustive: bool,
     pub transitions: Vec<StateTransition>,
     pub name: Option<Ident>,
     pub states_attr: Vec<Attribute>,
     pub events_attr: Vec<Attribute>,
+    pub entry_states: Vec<Ident>,
+    pub terminal_states: Vec<Ident>,
+    pub lints: Lints,
 }
 
 impl StateMachine {
@@ -16,10 +35,25 @@ impl StateMachine {
         StateMachine {
             temporary_context_type: None,
             custom_error: false,
+            async_send: false,
+            async_hooks: false,
+            entry_exit_event: false,
+            on_transition: false,
+            history: None,
+            history_timestamp_type: None,
+            metrics: false,
+            metrics_clock: false,
+            replay: false,
+            replay_span: None,
+            mock: false,
+            exhaustive: false,
             transitions: Vec::new(),
             name: None,
             states_attr: Vec::new(),
             events_attr: Vec::new(),
+            entry_states: Vec::new(),
+            terminal_states: Vec::new(),
+            lints: Lints::default(),
         }
     }
 
@@ -37,9 +71,18 @@ impl StateMachine {
     }
 }
 
+// Parses a list of states, e.g. `[State1, State2]`
+fn parse_state_list(input: parse::ParseStream) -> parse::Result<Vec<Ident>> {
+    let content;
+    bracketed!(content in input);
+    let states = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+    Ok(states.into_iter().collect())
+}
+
 impl parse::Parse for StateMachine {
     fn parse(input: parse::ParseStream) -> parse::Result<Self> {
         let mut statemachine = StateMachine::new();
+        let mut metrics_clock_span = None;
 
         loop {
             // If the last line ends with a comma this is true
@@ -79,6 +122,71 @@ impl parse::Parse for StateMachine {
                         statemachine.custom_error = true
                     }
                 }
+                "async_send" => {
+                    input.parse::<Token![:]>()?;
+                    let async_send: syn::LitBool = input.parse()?;
+                    statemachine.async_send = async_send.value;
+                }
+                "async_hooks" => {
+                    input.parse::<Token![:]>()?;
+                    let async_hooks: syn::LitBool = input.parse()?;
+                    statemachine.async_hooks = async_hooks.value;
+                }
+                "entry_exit_event" => {
+                    input.parse::<Token![:]>()?;
+                    let entry_exit_event: syn::LitBool = input.parse()?;
+                    statemachine.entry_exit_event = entry_exit_event.value;
+                }
+                "on_transition" => {
+                    input.parse::<Token![:]>()?;
+                    let on_transition: syn::LitBool = input.parse()?;
+                    statemachine.on_transition = on_transition.value;
+                }
+                "history" => {
+                    input.parse::<Token![:]>()?;
+                    let history: syn::LitInt = input.parse()?;
+                    let size = history.base10_parse::<usize>()?;
+                    if size == 0 {
+                        return Err(parse::Error::new(
+                            history.span(),
+                            "The history must hold at least one entry.",
+                        ));
+                    }
+                    statemachine.history = Some(size);
+                }
+                "history_timestamp" => {
+                    input.parse::<Token![:]>()?;
+                    statemachine.history_timestamp_type = Some(input.parse()?);
+                }
+                "metrics" => {
+                    input.parse::<Token![:]>()?;
+                    let metrics: syn::LitBool = input.parse()?;
+                    statemachine.metrics = metrics.value;
+                }
+                "metrics_clock" => {
+                    input.parse::<Token![:]>()?;
+                    let metrics_clock: syn::LitBool = input.parse()?;
+                    if metrics_clock.value {
+                        metrics_clock_span = Some(metrics_clock.span());
+                    }
+                    statemachine.metrics_clock = metrics_clock.value;
+                }
+                "replay" => {
+                    input.parse::<Token![:]>()?;
+                    let replay: syn::LitBool = input.parse()?;
+                    statemachine.replay = replay.value;
+                    statemachine.replay_span = Some(replay.span());
+                }
+                "mock" => {
+                    input.parse::<Token![:]>()?;
+                    let mock: syn::LitBool = input.parse()?;
+                    statemachine.mock = mock.value;
+                }
+                "exhaustive" => {
+                    input.parse::<Token![:]>()?;
+                    let exhaustive: syn::LitBool = input.parse()?;
+                    statemachine.exhaustive = exhaustive.value;
+                }
                 "temporary_context" => {
                     input.parse::<Token![:]>()?;
                     let temporary_context_type: Type = input.parse()?;
@@ -117,6 +225,21 @@ impl parse::Parse for StateMachine {
                     statemachine.events_attr = Attribute::parse_outer(input)?;
                 }
 
+                "entry_states" => {
+                    input.parse::<Token![:]>()?;
+                    statemachine.entry_states = parse_state_list(input)?;
+                }
+
+                "terminal_states" => {
+                    input.parse::<Token![:]>()?;
+                    statemachine.terminal_states = parse_state_list(input)?;
+                }
+
+                "lints" => {
+                    input.parse::<Token![:]>()?;
+                    statemachine.lints = input.parse()?;
+                }
+
                 keyword => {
                     return Err(parse::Error::new(
                         input.span(),
@@ -125,8 +248,22 @@ impl parse::Parse for StateMachine {
                                 \"transitions\", \
                                 \"temporary_context\", \
                                 \"custom_error\", \
+                                \"async_send\", \
+                                \"async_hooks\", \
+                                \"entry_exit_event\", \
+                                \"on_transition\", \
+                                \"history\", \
+                                \"history_timestamp\", \
+                                \"metrics\", \
+                                \"metrics_clock\", \
+                                \"replay\", \
+                                \"mock\", \
+                                \"exhaustive\", \
                                 \"states_attr\", \
-                                \"events_attr\"
+                                \"events_attr\", \
+                                \"entry_states\", \
+                                \"terminal_states\", \
+                                \"lints\"
                                 ]",
                             keyword
                         ),
@@ -144,6 +281,21 @@ impl parse::Parse for StateMachine {
             };
         }
 
+        if let (None, Some(timestamp_type)) =
+            (statemachine.history, &statemachine.history_timestamp_type)
+        {
+            return Err(parse::Error::new(
+                timestamp_type.span(),
+                "A history timestamp requires a history, add `history: N`.",
+            ));
+        }
+        if let (false, Some(span)) = (statemachine.metrics, metrics_clock_span) {
+            return Err(parse::Error::new(
+                span,
+                "A metrics clock requires metrics, add `metrics: true`.",
+            ));
+        }
+
         Ok(statemachine)
     }
 }
@@ -74,6 +74,21 @@ impl parse::Parse for StateTransitions {
 
         let out_state: OutputState = input.parse()?;
 
+        if let Some(action) = &action {
+            if out_state.ignore {
+                return Err(parse::Error::new(
+                    action.ident.span(),
+                    "Ignored events cannot have an action.",
+                ));
+            }
+            if out_state.reject.is_some() {
+                return Err(parse::Error::new(
+                    action.ident.span(),
+                    "Rejected events cannot have an action.",
+                ));
+            }
+        }
+
         Ok(Self {
             in_states,
             event,
@@ -135,6 +150,42 @@ impl GuardExpression {
     }
 }
 
+impl GuardExpression {
+    /// Returns the expression as written in the state machine definition, e.g.
+    /// `a && !(async b || c)`.
+    pub fn as_written(&self) -> String {
+        match self {
+            GuardExpression::Guard(async_ident) if async_ident.is_async => {
+                format!("async {}", async_ident.ident)
+            }
+            GuardExpression::Guard(async_ident) => async_ident.ident.to_string(),
+            GuardExpression::Not(expr) => format!("!{}", expr.as_written()),
+            GuardExpression::Group(expr) => format!("({})", expr.as_written()),
+            GuardExpression::And(lhs, rhs) => {
+                format!("{} && {}", lhs.as_written(), rhs.as_written())
+            }
+            GuardExpression::Or(lhs, rhs) => {
+                format!("{} || {}", lhs.as_written(), rhs.as_written())
+            }
+        }
+    }
+
+    /// Evaluates the expression as a boolean formula, with the result of each guard given by
+    /// `guard_value`.
+    pub fn eval<F>(&self, guard_value: &F) -> bool
+    where
+        F: Fn(&AsyncIdent) -> bool,
+    {
+        match self {
+            GuardExpression::Guard(async_ident) => guard_value(async_ident),
+            GuardExpression::Not(expr) => !expr.eval(guard_value),
+            GuardExpression::Group(expr) => expr.eval(guard_value),
+            GuardExpression::And(lhs, rhs) => lhs.eval(guard_value) && rhs.eval(guard_value),
+            GuardExpression::Or(lhs, rhs) => lhs.eval(guard_value) || rhs.eval(guard_value),
+        }
+    }
+}
+
 pub fn visit_guards<F>(expr: &GuardExpression, mut visit_guard: F) -> Result<(), parse::Error>
 where
     F: FnMut(&AsyncIdent) -> Result<(), parse::Error>,
@@ -250,4 +301,34 @@ mod test {
         }
         Ok(())
     }
+    #[test]
+    fn guard_expressions_as_written() -> Result<(), syn::Error> {
+        for guard_expression_str in [
+            "guard",
+            "async guard",
+            "!async a || b",
+            "a && (!b || async c) && d",
+        ] {
+            let guard_expression: GuardExpression = parse_str(guard_expression_str)?;
+            assert_eq!(guard_expression.as_written(), guard_expression_str);
+        }
+        Ok(())
+    }
+    #[test]
+    fn eval_guard_expressions() -> Result<(), syn::Error> {
+        let guard_value = |guard: &crate::parser::AsyncIdent| guard.ident == "a";
+        for (guard_expression_str, expected) in [
+            ("a", true),
+            ("b", false),
+            ("!a", false),
+            ("a && b", false),
+            ("a || b", true),
+            ("!(a && b)", true),
+            ("b || !a && c", false),
+        ] {
+            let guard_expression: GuardExpression = parse_str(guard_expression_str)?;
+            assert_eq!(guard_expression.eval(&guard_value), expected);
+        }
+        Ok(())
+    }
 }
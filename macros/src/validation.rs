@@ -1,8 +1,10 @@
+use crate::parser::event::{EventMapping, Transition};
+use crate::parser::lints::{Level, Lint};
 use crate::parser::transition::visit_guards;
 use crate::parser::{AsyncIdent, ParsedStateMachine};
 use proc_macro2::Span;
-use std::collections::HashMap;
-use syn::parse;
+use std::collections::{HashMap, HashSet};
+use syn::{parse, spanned::Spanned};
 
 /// A basic representation an action call signature.
 #[derive(PartialEq, Clone)]
@@ -51,142 +53,684 @@ impl FunctionSignature {
         // Guards never have output data.
         Self::new(input_state, event, None, is_async)
     }
+
+    pub fn new_hook(state: Option<&syn::Type>, event: Option<&syn::Type>, is_async: bool) -> Self {
+        // Entry and exit functions never have output data.
+        Self::new(state, event, None, is_async)
+    }
+}
+
+// Returns an error at `span`, with a note at the conflicting earlier definition.
+fn conflict(span: Span, message: String, earlier: Span, note: String) -> parse::Error {
+    let mut error = parse::Error::new(span, message);
+    error.combine(parse::Error::new(earlier, note));
+    error
 }
 
 // Verify action and guard function signatures.
 fn validate_action_signatures(sm: &ParsedStateMachine) -> Result<(), parse::Error> {
-    // Collect all of the action call signatures.
-    let mut actions = HashMap::new();
+    // Collect all of the action call signatures, with the first use of each action.
+    let mut actions: HashMap<String, (FunctionSignature, Span)> = HashMap::new();
 
-    let all_transitions = &sm.states_events_mapping;
+    for (event_mapping, transition) in sm.all_transitions() {
+        if let Some(AsyncIdent {
+            ident: action,
+            is_async,
+        }) = &transition.action
+        {
+            let signature = FunctionSignature::new(
+                sm.state_data
+                    .data_types
+                    .get(&event_mapping.in_state.to_string()),
+                sm.event_data
+                    .data_types
+                    .get(&event_mapping.event.to_string()),
+                sm.state_data
+                    .data_types
+                    .get(&transition.out_state.to_string()),
+                *is_async,
+            );
 
-    for (in_state_name, from_transitions) in all_transitions.iter() {
-        let in_state_data = sm.state_data.data_types.get(in_state_name);
+            // If the action is not yet known, add it to our tracking list.
+            let (recorded, first_use) = actions
+                .entry(action.to_string())
+                .or_insert_with(|| (signature.clone(), action.span()));
 
-        for (out_state_name, event_mapping) in from_transitions.iter() {
-            let out_state_data = sm.state_data.data_types.get(out_state_name);
+            // Check that the call signature is equivalent to the recorded signature for this
+            // action.
+            if recorded != &signature {
+                return Err(conflict(
+                    action.span(),
+                    format!("Action `{}` can only be reused when all input states, events, and output states have the same data", action),
+                    *first_use,
+                    format!("Action `{}` is first used here", action),
+                ));
+            }
+        }
+    }
 
-            // Get the data associated with this event.
+    Ok(())
+}
+
+fn validate_guard_signatures(sm: &ParsedStateMachine) -> Result<(), parse::Error> {
+    // Collect all of the guard call signatures, with the first use of each guard.
+    let mut guards: HashMap<String, (FunctionSignature, Span)> = HashMap::new();
+
+    for (event_mapping, transition) in sm.all_transitions() {
+        if let Some(guard_expression) = &transition.guard {
+            let in_state_data = sm
+                .state_data
+                .data_types
+                .get(&event_mapping.in_state.to_string());
             let event_data = sm
                 .event_data
                 .data_types
                 .get(&event_mapping.event.to_string());
-            for transition in &event_mapping.transitions {
-                if let Some(AsyncIdent {
-                    ident: action,
-                    is_async,
-                }) = &transition.action
-                {
-                    let signature = FunctionSignature::new(
-                        in_state_data,
-                        event_data,
-                        out_state_data,
-                        *is_async,
-                    );
-
-                    // If the action is not yet known, add it to our tracking list.
-                    actions
-                        .entry(action.to_string())
-                        .or_insert_with(|| signature.clone());
-
-                    // Check that the call signature is equivalent to the recorded signature for this
-                    // action.
-                    if actions.get(&action.to_string()).unwrap() != &signature {
-                        return Err(parse::Error::new(
-                            Span::call_site(),
-                            format!("Action `{}` can only be reused when all input states, events, and output states have the same data", action),
-                        ));
-                    }
+
+            visit_guards(guard_expression, |guard| {
+                let signature =
+                    FunctionSignature::new_guard(in_state_data, event_data, guard.is_async);
+
+                // If the guard is not yet known, add it to our tracking list.
+                let (recorded, first_use) = guards
+                    .entry(guard.ident.to_string())
+                    .or_insert_with(|| (signature.clone(), guard.ident.span()));
+
+                // Check that the call signature is equivalent to the recorded signature for this
+                // guard.
+                if recorded != &signature {
+                    return Err(conflict(
+                        guard.ident.span(),
+                        format!("Guard `{}` can only be reused when all input states and events have the same data", guard.ident),
+                        *first_use,
+                        format!("Guard `{}` is first used here", guard.ident),
+                    ));
                 }
+                Ok(())
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+// Verify that no action or guard is named like an entry or exit function, `on_transition`,
+// `history_timestamp` or `metrics_clock`, as they are all functions of the context trait.
+fn validate_hook_names(sm: &ParsedStateMachine) -> Result<(), parse::Error> {
+    let mut hooks = HashMap::new();
+    for (name, state) in &sm.states {
+        let name = string_morph::to_snake_case(name);
+        hooks.insert(format!("on_entry_{}", name), ("entry", state));
+        hooks.insert(format!("on_exit_{}", name), ("exit", state));
+    }
+
+    let check = |kind: &str, function: &syn::Ident| match hooks.get(&function.to_string()) {
+        _ if sm.on_transition && function == "on_transition" => Err(parse::Error::new(
+            function.span(),
+            format!(
+                "{} `{}` has the name of the transition hook",
+                kind, function
+            ),
+        )),
+        _ if sm.history_timestamp_type.is_some() && function == "history_timestamp" => {
+            Err(parse::Error::new(
+                function.span(),
+                format!(
+                    "{} `{}` has the name of the history timestamp function",
+                    kind, function
+                ),
+            ))
+        }
+        _ if sm.metrics_clock && function == "metrics_clock" => Err(parse::Error::new(
+            function.span(),
+            format!(
+                "{} `{}` has the name of the metrics clock function",
+                kind, function
+            ),
+        )),
+        Some((hook, state)) => Err(conflict(
+            function.span(),
+            format!(
+                "{} `{}` has the name of the {} function of state `{}`",
+                kind, function, hook, state
+            ),
+            state.span(),
+            format!("State `{}` is defined here", state),
+        )),
+        None => Ok(()),
+    };
+
+    for (_, transition) in sm.all_transitions() {
+        if let Some(guard_expression) = &transition.guard {
+            visit_guards(guard_expression, |guard| check("Guard", &guard.ident))?;
+        }
+        if let Some(action) = &transition.action {
+            check("Action", &action.ident)?;
+        }
+    }
+
+    Ok(())
+}
+
+// Verify that the entry and exit functions of different states, which are named after the states
+// in snake case, do not collide.
+fn validate_hook_signatures(sm: &ParsedStateMachine) -> Result<(), parse::Error> {
+    let sm_name = sm
+        .name
+        .as_ref()
+        .map(|name| name.to_string())
+        .unwrap_or_default();
+    let events_type: syn::Type = syn::parse_str(&format!("&{}Events", sm_name))?;
+    let event = if sm.entry_exit_event {
+        Some(&events_type)
+    } else {
+        None
+    };
+
+    let mut states: Vec<_> = sm.states.iter().collect();
+    states.sort_by_key(|(name, _)| *name);
+
+    let mut hooks: HashMap<String, (FunctionSignature, &syn::Ident)> = HashMap::new();
+    for (name, state) in states {
+        let signature =
+            FunctionSignature::new_hook(sm.state_data.data_types.get(name), event, sm.async_hooks);
+        let snake_case = string_morph::to_snake_case(name);
+        for (kind, hook) in [
+            ("entry", format!("on_entry_{}", snake_case)),
+            ("exit", format!("on_exit_{}", snake_case)),
+        ] {
+            if let Some((recorded, earlier)) = hooks.get(&hook) {
+                let difference = if *recorded == signature {
+                    ""
+                } else {
+                    ", with a different signature"
+                };
+                return Err(conflict(
+                    state.span(),
+                    format!(
+                        "The {} function `{}` of state `{}` is also the {} function of state `{}`{}, rename one of the states",
+                        kind, hook, state, kind, earlier, difference
+                    ),
+                    earlier.span(),
+                    format!("State `{}` is defined here", earlier),
+                ));
             }
+            hooks.insert(hook, (signature.clone(), state));
         }
     }
 
     Ok(())
 }
 
-fn validate_guard_signatures(sm: &ParsedStateMachine) -> Result<(), parse::Error> {
-    // Collect all of the guard call signatures.
-    let mut guards = HashMap::new();
+// Verify that the events of a state machine with `replay: true` can be stored in its event log,
+// which is not possible when they borrow data.
+fn validate_replay(sm: &ParsedStateMachine) -> Result<(), parse::Error> {
+    if !sm.replay {
+        return Ok(());
+    }
+    let mut borrowing: Vec<_> = sm.event_data.lifetimes.keys().collect();
+    borrowing.sort();
+    combine_errors(borrowing.into_iter().map(|event| {
+        parse::Error::new(
+            sm.event_data.data_types[event].span(),
+            format!(
+                "Event `{}` borrows data, which can not be recorded with `replay: true`",
+                event
+            ),
+        )
+    }))
+}
+
+// Verify that the mock context of a state machine with `mock: true` can store the data returned
+// by its actions, which is not possible when the states borrow data.
+fn validate_mock(sm: &ParsedStateMachine) -> Result<(), parse::Error> {
+    if !sm.mock {
+        return Ok(());
+    }
+    let mut borrowing: Vec<_> = sm.state_data.lifetimes.keys().collect();
+    borrowing.sort();
+    combine_errors(borrowing.into_iter().map(|state| {
+        parse::Error::new(
+            sm.state_data.data_types[state].span(),
+            format!(
+                "State `{}` borrows data, which can not be returned by the mock context of \
+                 `mock: true`",
+                state
+            ),
+        )
+    }))
+}
 
-    let all_transitions = &sm.states_events_mapping;
+// Returns the event mappings in the order they are defined, which keeps the order of reported
+// errors stable.
+fn sorted_event_mappings(sm: &ParsedStateMachine) -> Vec<&EventMapping> {
+    let mut event_mappings: Vec<_> = sm
+        .states_events_mapping
+        .values()
+        .flat_map(|event_mappings| event_mappings.values())
+        .collect();
+    event_mappings.sort_by_key(|event_mapping| event_mapping.transitions[0].position);
+    event_mappings
+}
 
-    for (in_state_name, from_transitions) in all_transitions.iter() {
-        let in_state_data = sm.state_data.data_types.get(in_state_name);
+/// Lint findings, i.e. errors or warnings depending on the configured level of the lint.
+pub type Findings = Vec<(Lint, parse::Error)>;
 
-        for (_out_state_name, event_mapping) in from_transitions.iter() {
-            // Get the data associated with this event.
-            let event_data = sm
-                .event_data
-                .data_types
-                .get(&event_mapping.event.to_string());
-            for transition in &event_mapping.transitions {
-                if let Some(guard_expression) = &transition.guard {
-                    let res = visit_guards(guard_expression, |guard| {
-                        let signature =
-                            FunctionSignature::new_guard(in_state_data, event_data, guard.is_async);
-
-                        // If the action is not yet known, add it to our tracking list.
-                        guards
-                            .entry(guard.ident.to_string())
-                            .or_insert_with(|| signature.clone());
-
-                        // Check that the call signature is equivalent to the recorded signature for this
-                        // guard.
-                        if guards.get(&guard.ident.to_string()).unwrap() != &signature {
-                            return Err(parse::Error::new(
-                                Span::call_site(),
-                                format!("Guard `{}` can only be reused when all input states and events have the same data", guard.ident),
-                            ));
-                        }
-                        Ok(())
-                    });
-                    res?;
+fn validate_unreachable_transitions(sm: &ParsedStateMachine) -> Findings {
+    let mut findings = Vec::new();
+
+    // Report the conflicts in the order they are defined
+    let event_mappings = sorted_event_mappings(sm)
+        .into_iter()
+        .filter(|event_mapping| event_mapping.transitions.len() > 1);
+
+    for event_mapping in event_mappings {
+        let in_state = &event_mapping.in_state;
+        let event = &event_mapping.event;
+
+        // more than single transition for (in_state,event)
+        let mut unguarded = None;
+        for t in &event_mapping.transitions {
+            if let Some(g) = &t.guard {
+                if let Some(unguarded) = unguarded {
+                    // Guarded transition AFTER an unguarded one
+                    findings.push((Lint::UnreachableTransition, conflict(
+                        t.span,
+                        format!("{} + {}: [{}] : guarded transition is unreachable because it follows an unguarded transition, which handles all cases",
+                                in_state, event, g),
+                        unguarded,
+                        format!("{} + {}: the unguarded transition is defined here", in_state, event),
+                    )));
+                    break;
                 }
+            } else if let Some(unguarded) = unguarded {
+                findings.push((Lint::UnreachableTransition, conflict(
+                    t.span,
+                    format!("{} + {}: State and event combination specified multiple times, remove duplicates.", in_state, event),
+                    unguarded,
+                    format!("{} + {}: first defined here", in_state, event),
+                )));
+                break;
+            } else {
+                unguarded = Some(t.span);
             }
         }
     }
+    findings
+}
 
-    Ok(())
+// Guard expressions are analysed by evaluating them for every combination of guard results, which
+// is only feasible for a limited number of distinct guards.
+const MAX_ANALYSED_GUARDS: usize = 12;
+
+// Describes a transition in error messages.
+fn describe(event_mapping: &EventMapping, transition: &Transition) -> String {
+    match &transition.guard {
+        Some(guard) => format!(
+            "{} + {}: [{}] :",
+            event_mapping.in_state, event_mapping.event, guard
+        ),
+        None => format!("{} + {}:", event_mapping.in_state, event_mapping.event),
+    }
 }
-fn validate_unreachable_transitions(sm: &ParsedStateMachine) -> Result<(), parse::Error> {
-    let all_transitions = &sm.states_events_mapping;
-    for (in_state, event_mappings) in all_transitions {
-        for (event, event_mapping) in event_mappings {
-            // more than single transition for (in_state,event)
-            if event_mapping.transitions.len() > 1 {
-                let mut unguarded_count = 0;
-                for t in &event_mapping.transitions {
-                    if let Some(g) = &t.guard {
-                        if unguarded_count > 0 {
-                            // Guarded transition AFTER an unguarded one
-                            return Err(parse::Error::new(
-                                Span::call_site(),
-                                format!("{} + {}: [{}] : guarded transition is unreachable because it follows an unguarded transition, which handles all cases",
-                                        in_state, event, g),
-                            ));
-                        }
-                    } else {
-                        // unguarded
-                        unguarded_count += 1;
-                        if unguarded_count > 1 {
-                            return Err(parse::Error::new(
-                                Span::call_site(),
-                                format!("{} + {}: State and event combination specified multiple times, remove duplicates.", in_state, event),
-                            ));
-                        }
+
+// Verify the guard expressions of the transitions sharing an input state and event. Guards are
+// treated as boolean variables, so guards which can never be true are rejected, as well as
+// transitions which can never be the first enabled one because an earlier guard is always true or
+// equivalent, or because the earlier guards together handle all cases.
+fn validate_guard_expressions(sm: &ParsedStateMachine) -> Result<Findings, parse::Error> {
+    let mut findings = Vec::new();
+
+    for event_mapping in sorted_event_mappings(sm) {
+        let transitions = &event_mapping.transitions;
+
+        // Collect the guards used by any of the transitions
+        let mut guards: Vec<String> = Vec::new();
+        for transition in transitions {
+            if let Some(guard_expression) = &transition.guard {
+                visit_guards(guard_expression, |guard| {
+                    let name = guard.ident.to_string();
+                    if !guards.contains(&name) {
+                        guards.push(name);
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+        if guards.is_empty() || guards.len() > MAX_ANALYSED_GUARDS {
+            continue;
+        }
+
+        // The result of every transition's guard for every combination of guard results, where
+        // bit `n` of the combination is the result of guard `n`. Unguarded transitions are always
+        // enabled.
+        let truth_tables: Vec<Vec<bool>> = transitions
+            .iter()
+            .map(|transition| {
+                (0..1usize << guards.len())
+                    .map(|results| match &transition.guard {
+                        Some(guard_expression) => guard_expression.eval(&|guard| {
+                            let n = guards.iter().position(|name| guard.ident == name).unwrap();
+                            results & (1 << n) != 0
+                        }),
+                        None => true,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        for (j, transition) in transitions.iter().enumerate() {
+            let table = &truth_tables[j];
+            let earlier = &truth_tables[..j];
+
+            // Transitions following an unguarded transition are reported by
+            // `validate_unreachable_transitions`
+            if transitions[..j].iter().any(|other| other.guard.is_none()) {
+                break;
+            }
+
+            if !table.iter().any(|enabled| *enabled) {
+                findings.push((
+                    Lint::ContradictoryGuard,
+                    parse::Error::new(
+                        transition.span,
+                        format!(
+                            "{} guard can never be true, so the transition is unreachable",
+                            describe(event_mapping, transition)
+                        ),
+                    ),
+                ));
+            } else if let Some(i) = earlier
+                .iter()
+                .position(|other| other.iter().all(|enabled| *enabled))
+            {
+                findings.push((
+                    Lint::UnreachableTransition,
+                    conflict(
+                        transition.span,
+                        format!(
+                            "{} transition is unreachable because an earlier guard is always true",
+                            describe(event_mapping, transition)
+                        ),
+                        transitions[i].span,
+                        format!(
+                            "{} this guard is always true",
+                            describe(event_mapping, &transitions[i])
+                        ),
+                    ),
+                ));
+            } else if let Some(i) = earlier.iter().position(|other| other == table) {
+                findings.push((Lint::UnreachableTransition, conflict(
+                    transition.span,
+                    format!(
+                        "{} transition is unreachable because an earlier transition has an equivalent guard",
+                        describe(event_mapping, transition)
+                    ),
+                    transitions[i].span,
+                    format!(
+                        "{} the equivalent guard is defined here",
+                        describe(event_mapping, &transitions[i])
+                    ),
+                )));
+            } else if j > 0
+                && (0..table.len())
+                    .all(|results| !table[results] || earlier.iter().any(|other| other[results]))
+            {
+                let mut error = parse::Error::new(
+                    transition.span,
+                    format!(
+                        "{} transition is unreachable because the earlier guards handle all cases in which it is true",
+                        describe(event_mapping, transition)
+                    ),
+                );
+                for (i, other) in earlier.iter().enumerate() {
+                    if (0..table.len()).any(|results| table[results] && other[results]) {
+                        error.combine(parse::Error::new(
+                            transitions[i].span,
+                            format!(
+                                "{} overlapping guard defined here",
+                                describe(event_mapping, &transitions[i])
+                            ),
+                        ));
                     }
                 }
+                findings.push((Lint::UnreachableTransition, error));
             }
         }
     }
-    Ok(())
+
+    Ok(findings)
+}
+
+/// Returns the names of all states which can be entered from the starting state or from one of
+/// the declared entry states.
+pub fn reachable_states(sm: &ParsedStateMachine) -> HashSet<String> {
+    let mut reachable = HashSet::new();
+    let mut stack: Vec<String> = std::iter::once(&sm.starting_state)
+        .chain(sm.entry_states.iter())
+        .map(|state| state.to_string())
+        .collect();
+
+    while let Some(state) = stack.pop() {
+        if !reachable.insert(state.clone()) {
+            continue;
+        }
+        if let Some(event_mappings) = sm.states_events_mapping.get(&state) {
+            for event_mapping in event_mappings.values() {
+                for transition in &event_mapping.transitions {
+                    stack.push(transition.out_state.to_string());
+                }
+            }
+        }
+    }
+
+    reachable
+}
+
+// Combines all errors into a single error, so they are all reported at once.
+fn combine_errors(errors: impl IntoIterator<Item = parse::Error>) -> Result<(), parse::Error> {
+    let mut errors = errors.into_iter();
+    match errors.next() {
+        Some(mut error) => {
+            errors.for_each(|e| error.combine(e));
+            Err(error)
+        }
+        None => Ok(()),
+    }
+}
+
+// Verify that all states in a list of states (e.g. `entry_states`) are known.
+fn validate_state_list(
+    sm: &ParsedStateMachine,
+    states: &[syn::Ident],
+    kind: &str,
+) -> Result<(), parse::Error> {
+    combine_errors(
+        states
+            .iter()
+            .filter(|state| !sm.states.contains_key(&state.to_string()))
+            .map(|state| parse::Error::new(state.span(), format!("Unknown {} `{}`", kind, state))),
+    )
 }
 
-/// Validate coherency of the state machine.
-pub fn validate(sm: &ParsedStateMachine) -> Result<(), parse::Error> {
+// Returns the states sorted by name, which keeps the order of reported errors stable.
+fn sorted_states(
+    sm: &ParsedStateMachine,
+    filter: impl Fn(&str) -> bool,
+) -> Vec<(&String, &syn::Ident)> {
+    let mut states: Vec<_> = sm.states.iter().filter(|(name, _)| filter(name)).collect();
+    states.sort_by_key(|(name, _)| *name);
+    states
+}
+
+// Verify that every state can be entered. States which are only entered through
+// `new_with_state` have to be declared as entry states.
+fn validate_unreachable_states(sm: &ParsedStateMachine) -> Result<Findings, parse::Error> {
+    validate_state_list(sm, &sm.entry_states, "entry state")?;
+
+    let reachable = reachable_states(sm);
+    Ok(sorted_states(sm, |name| !reachable.contains(name))
+        .into_iter()
+        .map(|(name, state)| {
+            (
+                Lint::UnreachableState,
+                parse::Error::new(
+                    state.span(),
+                    format!(
+                        "State `{}` is unreachable from the starting state. If it is only entered \
+                         through `new_with_state`, add it to `entry_states`.",
+                        name
+                    ),
+                ),
+            )
+        })
+        .collect())
+}
+
+// Verify that the state machine cannot get stuck, i.e. that a terminal state can be reached from
+// every state. This is only checked when terminal states have been declared.
+fn validate_dead_ends(sm: &ParsedStateMachine) -> Result<Findings, parse::Error> {
+    if sm.terminal_states.is_empty() {
+        return Ok(Vec::new());
+    }
+    validate_state_list(sm, &sm.terminal_states, "terminal state")?;
+
+    let terminal: HashSet<String> = sm.terminal_states.iter().map(|s| s.to_string()).collect();
+    let is_stuck = |name: &str| {
+        !terminal.contains(name)
+            && sm.states_events_mapping[name]
+                .values()
+                .all(|event_mapping| event_mapping.transitions.iter().all(|t| !t.is_transition()))
+    };
+
+    // Non-terminal states without any outgoing transitions
+    let mut findings: Findings = sorted_states(sm, is_stuck)
+        .into_iter()
+        .map(|(name, state)| {
+            (
+                Lint::DeadEnd,
+                parse::Error::new(
+                    state.span(),
+                    format!(
+                        "State `{}` has no outgoing transitions, the state machine gets stuck \
+                         here. If this is intended, add it to `terminal_states`.",
+                        name
+                    ),
+                ),
+            )
+        })
+        .collect();
+
+    // States from which no terminal state can be reached, e.g. cycles without an exit. The stuck
+    // states are already reported, so the states leading to them are not.
+    let mut can_terminate: HashSet<String> = sm
+        .states_events_mapping
+        .keys()
+        .filter(|name| terminal.contains(*name) || is_stuck(name))
+        .cloned()
+        .collect();
+    loop {
+        let before = can_terminate.len();
+        for (state, event_mappings) in &sm.states_events_mapping {
+            let leads_to_terminal = event_mappings.values().any(|event_mapping| {
+                event_mapping
+                    .transitions
+                    .iter()
+                    .any(|transition| can_terminate.contains(&transition.out_state.to_string()))
+            });
+            if leads_to_terminal {
+                can_terminate.insert(state.clone());
+            }
+        }
+        if can_terminate.len() == before {
+            break;
+        }
+    }
+
+    findings.extend(
+        sorted_states(sm, |name| !can_terminate.contains(name))
+            .into_iter()
+            .map(|(name, state)| {
+                (
+                    Lint::DeadEnd,
+                    parse::Error::new(
+                        state.span(),
+                        format!(
+                            "No terminal state can be reached from state `{}`, the state \
+                             machine gets stuck in a cycle.",
+                            name
+                        ),
+                    ),
+                )
+            }),
+    );
+
+    Ok(findings)
+}
+
+// Verify that every state either handles or explicitly ignores every event.
+fn validate_exhaustive(sm: &ParsedStateMachine) -> Findings {
+    let mut events: Vec<_> = sm.events.keys().collect();
+    events.sort();
+
+    sorted_states(sm, |_| true)
+        .into_iter()
+        .flat_map(|(name, state)| {
+            events
+                .iter()
+                .filter(move |event| !sm.states_events_mapping[name].contains_key(**event))
+                .map(move |event| {
+                    (
+                        Lint::NonExhaustive,
+                        parse::Error::new(
+                            state.span(),
+                            format!(
+                                "State `{0}` does not handle event `{1}`. Add a transition, or \
+                                 ignore the event explicitly with `{0} + {1} = ignore`.",
+                                name, event
+                            ),
+                        ),
+                    )
+                })
+        })
+        .collect()
+}
+
+// Appends the name of the lint to the message of a finding, so it can be configured.
+fn with_lint_name(lint: Lint, error: parse::Error) -> parse::Error {
+    let mut parts = error.into_iter();
+    let first = parts.next().unwrap();
+    let mut error = parse::Error::new(first.span(), format!("{} (lint `{}`)", first, lint.name()));
+    parts.for_each(|part| error.combine(part));
+    error
+}
+
+/// Validate coherency of the state machine. Findings of lints configured to `warn` are returned,
+/// so they can be reported as warnings.
+pub fn validate(sm: &ParsedStateMachine) -> Result<Findings, parse::Error> {
     validate_action_signatures(sm)?;
     validate_guard_signatures(sm)?;
-    validate_unreachable_transitions(sm)?;
-    Ok(())
+    validate_hook_names(sm)?;
+    validate_hook_signatures(sm)?;
+    validate_replay(sm)?;
+    validate_mock(sm)?;
+
+    let mut findings = validate_unreachable_transitions(sm);
+    findings.extend(validate_guard_expressions(sm)?);
+    findings.extend(validate_unreachable_states(sm)?);
+    findings.extend(validate_dead_ends(sm)?);
+    findings.extend(validate_exhaustive(sm));
+
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    for (lint, finding) in findings {
+        match sm.lints.level(lint) {
+            Level::Allow => {}
+            Level::Warn => warnings.push((lint, with_lint_name(lint, finding))),
+            Level::Deny => errors.push(with_lint_name(lint, finding)),
+        }
+    }
+    combine_errors(errors)?;
+
+    Ok(warnings)
 }
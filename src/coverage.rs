@@ -0,0 +1,143 @@
+//! The transitions taken by state machines, recorded with the `coverage` feature.
+
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::TransitionInfo;
+
+/// Marks of the transitions taken by all instances of a state machine, indexed like its
+/// `TRANSITIONS` table.
+///
+/// The marks are atomic, so the instances of tests running in parallel share them.
+pub struct Coverage<const N: usize> {
+    taken: [AtomicBool; N],
+}
+
+impl<const N: usize> Coverage<N> {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const NOT_TAKEN: AtomicBool = AtomicBool::new(false);
+
+    /// Creates marks without any transition taken.
+    pub const fn new() -> Self {
+        Coverage {
+            taken: [Self::NOT_TAKEN; N],
+        }
+    }
+
+    /// Marks a transition as taken.
+    pub fn mark(&self, transition: usize) {
+        self.taken[transition].store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if the transition was taken.
+    pub fn is_taken(&self, transition: usize) -> bool {
+        self.taken[transition].load(Ordering::Relaxed)
+    }
+
+    /// Clears the marks of all transitions.
+    pub fn reset(&self) {
+        for taken in &self.taken {
+            taken.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns a report of the marks, with the `TRANSITIONS` table of the state machine.
+    pub fn report<'a>(&'a self, transitions: &'a [TransitionInfo; N]) -> Report<'a> {
+        Report {
+            taken: &self.taken,
+            transitions,
+        }
+    }
+}
+
+impl<const N: usize> Default for Coverage<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Debug for Coverage<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.taken.iter().map(|taken| taken.load(Ordering::Relaxed)))
+            .finish()
+    }
+}
+
+/// The transitions of a state machine which were taken and which were not, returned by the
+/// `coverage` function of state machines generated with the `coverage` feature.
+///
+/// It displays the number of covered transitions followed by every uncovered transition, one per
+/// line as `State + Event [guard] = Target`, without its action.
+#[derive(Clone, Copy)]
+pub struct Report<'a> {
+    taken: &'a [AtomicBool],
+    transitions: &'a [TransitionInfo],
+}
+
+impl<'a> Report<'a> {
+    /// Returns an iterator over the transitions which were taken.
+    pub fn covered(&self) -> impl Iterator<Item = &'a TransitionInfo> + '_ {
+        self.filter(true)
+    }
+
+    /// Returns an iterator over the transitions which were never taken.
+    pub fn uncovered(&self) -> impl Iterator<Item = &'a TransitionInfo> + '_ {
+        self.filter(false)
+    }
+
+    /// Returns the percentage of the transitions which were taken, 100 for a state machine
+    /// without transitions.
+    pub fn percent(&self) -> f64 {
+        if self.transitions.is_empty() {
+            return 100.0;
+        }
+        self.covered().count() as f64 * 100.0 / self.transitions.len() as f64
+    }
+
+    /// Panics with the report if less than `min_percent` percent of the transitions were taken,
+    /// to fail a test.
+    #[track_caller]
+    pub fn assert_min(&self, min_percent: f64) {
+        if self.percent() < min_percent {
+            panic!("transition coverage is below {}%: {}", min_percent, self);
+        }
+    }
+
+    fn filter(&self, taken: bool) -> impl Iterator<Item = &'a TransitionInfo> + '_ {
+        self.transitions
+            .iter()
+            .zip(self.taken)
+            .filter(move |(_, marked)| marked.load(Ordering::Relaxed) == taken)
+            .map(|(transition, _)| transition)
+    }
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} transitions covered ({:.1}%)",
+            self.covered().count(),
+            self.transitions.len(),
+            self.percent()
+        )?;
+        for transition in self.uncovered() {
+            write!(f, "\n  {} + {}", transition.from, transition.event)?;
+            if let Some(guard) = transition.guard {
+                write!(f, " [{}]", guard)?;
+            }
+            write!(f, " = {}", transition.to)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Report")
+            .field("covered", &self.covered().count())
+            .field("transitions", &self.transitions.len())
+            .finish()
+    }
+}
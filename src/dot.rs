@@ -0,0 +1,72 @@
+//! Rendering of state machines as graphviz dot diagrams at runtime.
+
+use crate::TransitionInfo;
+use core::fmt::{self, Write};
+
+/// The static structure of a state machine, embedded in the generated code.
+#[derive(Debug, Clone, Copy)]
+pub struct Diagram {
+    /// Name of the graph.
+    pub name: &'static str,
+    /// Names of all states.
+    pub states: &'static [&'static str],
+    /// Name of the starting state.
+    pub starting_state: &'static str,
+    /// All transitions of the state machine.
+    pub transitions: &'static [TransitionInfo],
+}
+
+impl Diagram {
+    /// Writes the diagram in dot syntax, with the `current` state highlighted and the transition
+    /// at index `last` (if any) emphasised.
+    pub fn write(&self, w: &mut impl Write, current: &str, last: Option<usize>) -> fmt::Result {
+        writeln!(w, "digraph {} {{", self.name)?;
+        writeln!(w, "    rankdir=\"LR\";")?;
+        writeln!(w, "    node [fontname=Arial];")?;
+        writeln!(w, "    edge [fontname=Arial];")?;
+        writeln!(
+            w,
+            "    s [shape=circle size=2 color=\"black\" style=filled label=\"\"]"
+        )?;
+        writeln!(w, "    s -> {}", self.starting_state)?;
+        writeln!(w)?;
+
+        for state in self.states {
+            if *state == current {
+                writeln!(
+                    w,
+                    "    {} [shape=box color=\"black\" fillcolor=\"#66cc66\" penwidth=3 style=filled]",
+                    state
+                )?;
+            } else {
+                writeln!(
+                    w,
+                    "    {} [shape=box color=\"red\" fillcolor=\"#ffbb33\" style=filled]",
+                    state
+                )?;
+            }
+        }
+        writeln!(w)?;
+
+        for (index, transition) in self.transitions.iter().enumerate() {
+            write!(
+                w,
+                "    {} -> {} [label=\"{}",
+                transition.from, transition.to, transition.event
+            )?;
+            if let Some(guard) = transition.guard {
+                write!(w, "\\n[{}]", guard)?;
+            }
+            if let Some(action) = transition.action {
+                write!(w, " / {}", action)?;
+            }
+            if last == Some(index) {
+                writeln!(w, "\" color=\"black\" penwidth=3];")?;
+            } else {
+                writeln!(w, "\" color=blue];")?;
+            }
+        }
+
+        writeln!(w, "}}")
+    }
+}
@@ -0,0 +1,103 @@
+//! The history of the events processed by state machines with `history: N`.
+
+/// The outcome of processing an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Outcome {
+    /// The state machine transitioned to another state.
+    Transition,
+    /// The event was handled without leaving the state: an internal transition, a transition to
+    /// the same state or an ignored event.
+    Internal,
+    /// Processing the event returned an error, for example because it was invalid in the state
+    /// or a guard or action failed.
+    Failed,
+}
+
+/// An event processed by a state machine, recorded in its [`History`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HistoryEntry<S, E, T> {
+    /// The kind of the state the event was processed in.
+    pub from: S,
+    /// The kind of the event.
+    pub event: E,
+    /// The kind of the state after processing the event.
+    pub to: S,
+    /// The outcome of processing the event.
+    pub outcome: Outcome,
+    /// The timestamp returned by the `history_timestamp` function of the context, or `()`.
+    pub timestamp: T,
+}
+
+/// The last `N` events processed by a state machine, in a ring buffer which does not allocate.
+#[derive(Debug, Clone)]
+pub struct History<S, E, T, const N: usize> {
+    entries: [Option<HistoryEntry<S, E, T>>; N],
+    // The index of the oldest entry
+    head: usize,
+    len: usize,
+}
+
+impl<S: Copy, E: Copy, T: Copy, const N: usize> History<S, E, T, N> {
+    /// Creates an empty history.
+    pub const fn new() -> Self {
+        History {
+            entries: [None; N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Records an entry, replacing the oldest one if the history is full.
+    pub fn push(&mut self, entry: HistoryEntry<S, E, T>) {
+        if N == 0 {
+            return;
+        }
+        let index = (self.head + self.len) % N;
+        self.entries[index] = Some(entry);
+        if self.len == N {
+            self.head = (self.head + 1) % N;
+        } else {
+            self.len += 1;
+        }
+    }
+
+    /// Returns an iterator over the entries, from the oldest to the newest.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &HistoryEntry<S, E, T>> + '_ {
+        (0..self.len).filter_map(move |offset| self.entries[(self.head + offset) % N].as_ref())
+    }
+
+    /// Returns the newest entry, if any.
+    pub fn last(&self) -> Option<&HistoryEntry<S, E, T>> {
+        self.iter().next_back()
+    }
+
+    /// Returns the number of recorded entries.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no entries are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of entries the history can hold.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.entries = [None; N];
+        self.head = 0;
+        self.len = 0;
+    }
+}
+
+impl<S: Copy, E: Copy, T: Copy, const N: usize> Default for History<S, E, T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -81,4 +81,43 @@
 //! ```
 #![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod coverage;
+pub mod dot;
+pub mod history;
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod mock;
+mod process_event;
+#[cfg(feature = "std")]
+pub mod replay;
+pub mod runner;
+#[cfg(feature = "critical-section")]
+mod shared;
+mod transition;
+pub mod walk;
+
+// Used by the generated implementations of `Arbitrary`
+#[cfg(feature = "arbitrary")]
+#[doc(hidden)]
+pub use arbitrary;
+#[cfg(feature = "proptest")]
+#[doc(hidden)]
+pub use proptest;
+// Used by the generated logging functions
+#[cfg(feature = "defmt")]
+#[doc(hidden)]
+pub use defmt;
+#[cfg(feature = "log")]
+#[doc(hidden)]
+pub use log;
+pub use process_event::{ProcessEvent, ProcessEventAsync, ProcessEventSend};
+#[cfg(feature = "critical-section")]
+pub use shared::SharedStateMachine;
 pub use smlang_macros::statemachine;
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use tracing;
+pub use transition::{TransitionInfo, TransitionRecord};
@@ -0,0 +1,79 @@
+//! The counters of state machines with `metrics: true`.
+
+/// Counters of the transitions and states of a state machine, sized by the state machine macro.
+///
+/// `transitions` is indexed like the `TRANSITIONS` table of the state machine, the other arrays
+/// like its `STATE_NAMES`, which is also the order of the `index` of its `StateKind`. The
+/// counters saturate instead of overflowing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metrics<const T: usize, const S: usize> {
+    /// How often each transition was taken, including internal transitions.
+    pub transitions: [u32; T],
+    /// How often each state was entered from another state.
+    pub entries: [u32; S],
+    /// How many events each state rejected, because they were invalid or explicitly rejected in
+    /// the state, or because all guards of the event failed.
+    pub rejected: [u32; S],
+    /// The time spent in each state up to the last processed event, in the unit of the clock of
+    /// state machines with `metrics_clock: true`.
+    pub time_in_state: [u64; S],
+    // The time the state machine was created or reset, or of the last processed event
+    last_time: Option<u64>,
+}
+
+impl<const T: usize, const S: usize> Metrics<T, S> {
+    /// Creates metrics with all counters at zero.
+    pub const fn new() -> Self {
+        Metrics {
+            transitions: [0; T],
+            entries: [0; S],
+            rejected: [0; S],
+            time_in_state: [0; S],
+            last_time: None,
+        }
+    }
+
+    /// Creates metrics with all counters at zero, which count the time spent in the current
+    /// state from `now`.
+    pub const fn started_at(now: u64) -> Self {
+        let mut metrics = Self::new();
+        metrics.last_time = Some(now);
+        metrics
+    }
+
+    /// Counts a transition.
+    pub fn record_transition(&mut self, transition: usize) {
+        self.transitions[transition] = self.transitions[transition].saturating_add(1);
+    }
+
+    /// Counts an entry into a state.
+    pub fn record_entry(&mut self, state: usize) {
+        self.entries[state] = self.entries[state].saturating_add(1);
+    }
+
+    /// Counts an event rejected in a state.
+    pub fn record_rejected(&mut self, state: usize) {
+        self.rejected[state] = self.rejected[state].saturating_add(1);
+    }
+
+    /// Adds the time since the last call, or since the metrics were [`started_at`](Self::started_at),
+    /// to the time spent in the state. Without a start, the time of the first call is only stored.
+    pub fn record_time(&mut self, state: usize, now: u64) {
+        if let Some(last_time) = self.last_time {
+            self.time_in_state[state] =
+                self.time_in_state[state].saturating_add(now.saturating_sub(last_time));
+        }
+        self.last_time = Some(now);
+    }
+
+    /// Sets all counters to zero.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl<const T: usize, const S: usize> Default for Metrics<T, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,113 @@
+//! The context of state machines with `mock: true` in unit tests.
+
+use core::any::Any;
+use core::cell::RefCell;
+use core::fmt;
+use std::boxed::Box;
+use std::collections::HashMap;
+use std::vec::Vec;
+
+/// A function of the context called by a state machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Call {
+    /// A guard, by name.
+    Guard(&'static str),
+    /// An action, by name.
+    Action(&'static str),
+    /// The entry function of a state, by the name of the state.
+    Entry(&'static str),
+    /// The exit function of a state, by the name of the state.
+    Exit(&'static str),
+}
+
+/// A context which records its calls, for testing the transitions of a state machine.
+///
+/// State machines with `mock: true` implement their context trait for `Mock`, and name it
+/// `MockContext`. Guards return the configured results and actions the configured data of their
+/// target state, a guard or an action returning data without a configuration panics. Entry and
+/// exit functions and actions without data only record their call.
+#[derive(Default)]
+pub struct Mock {
+    guards: HashMap<&'static str, bool>,
+    actions: HashMap<&'static str, Box<dyn Any>>,
+    calls: RefCell<Vec<Call>>,
+}
+
+impl Mock {
+    /// Creates a mock without configured guards and actions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Configures the result of a guard.
+    pub fn set_guard(&mut self, guard: &'static str, passed: bool) -> &mut Self {
+        self.guards.insert(guard, passed);
+        self
+    }
+
+    /// Configures the data returned by an action, which is cloned for every call.
+    pub fn set_action<T: Clone + 'static>(&mut self, action: &'static str, data: T) -> &mut Self {
+        self.actions.insert(action, Box::new(data));
+        self
+    }
+
+    /// Returns the recorded calls, from the oldest to the newest.
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.borrow().clone()
+    }
+
+    /// Returns and removes the recorded calls.
+    pub fn take_calls(&mut self) -> Vec<Call> {
+        self.calls.get_mut().drain(..).collect()
+    }
+
+    /// Records a call, used by the generated implementation of the context.
+    pub fn record(&self, call: Call) {
+        self.calls.borrow_mut().push(call);
+    }
+
+    /// Records the call of a guard and returns its result, used by the generated implementation
+    /// of the context.
+    pub fn guard(&self, guard: &'static str) -> bool {
+        self.record(Call::Guard(guard));
+        match self.guards.get(guard) {
+            Some(passed) => *passed,
+            None => panic!(
+                "no result is configured for guard `{}`, see `Mock::set_guard`",
+                guard
+            ),
+        }
+    }
+
+    /// Records the call of an action and returns its data, used by the generated implementation
+    /// of the context.
+    pub fn action<T: Clone + 'static>(&self, action: &'static str) -> T {
+        self.record(Call::Action(action));
+        match self.actions.get(action) {
+            Some(data) => match data.downcast_ref::<T>() {
+                Some(data) => data.clone(),
+                None => panic!(
+                    "the data configured for action `{}` is not a `{}`",
+                    action,
+                    core::any::type_name::<T>()
+                ),
+            },
+            None => panic!(
+                "no data is configured for action `{}`, see `Mock::set_action`",
+                action
+            ),
+        }
+    }
+}
+
+impl fmt::Debug for Mock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut actions: Vec<_> = self.actions.keys().collect();
+        actions.sort();
+        f.debug_struct("Mock")
+            .field("guards", &self.guards)
+            .field("actions", &actions)
+            .field("calls", &self.calls.borrow())
+            .finish()
+    }
+}
@@ -0,0 +1,50 @@
+use core::future::Future;
+
+/// A state machine which processes events, implemented by every generated state machine which is
+/// not `async`.
+///
+/// This allows writing code which drives any state machine, like the [`Runner`](crate::runner).
+/// State machines with a `temporary_context`, or with events borrowing data for a lifetime the
+/// states do not use, do not implement this trait.
+pub trait ProcessEvent {
+    /// The events of the state machine.
+    type Event;
+    /// The error returned when processing an event fails.
+    type Error;
+
+    /// Processes an event, see the `process_event` method of the generated state machine.
+    fn process_event(&mut self, event: Self::Event) -> Result<(), Self::Error>;
+}
+
+/// The `async` version of [`ProcessEvent`], implemented by every generated state machine which
+/// is `async`.
+pub trait ProcessEventAsync {
+    /// The events of the state machine.
+    type Event;
+    /// The error returned when processing an event fails.
+    type Error;
+
+    /// Processes an event, see the `process_event` method of the generated state machine.
+    fn process_event(
+        &mut self,
+        event: Self::Event,
+    ) -> impl Future<Output = Result<(), Self::Error>>;
+}
+
+/// The `Send` version of [`ProcessEventAsync`], implemented by every generated state machine with
+/// `async_send: true` which is `Send` and whose events are `Send`.
+///
+/// Generic code bounded by this trait, like [`Runner::run_async_send`](crate::runner::Runner::run_async_send),
+/// returns futures which can be spawned on multi-threaded executors.
+pub trait ProcessEventSend: Send {
+    /// The events of the state machine.
+    type Event: Send;
+    /// The error returned when processing an event fails.
+    type Error;
+
+    /// Processes an event, see the `process_event` method of the generated state machine.
+    fn process_event(
+        &mut self,
+        event: Self::Event,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+}
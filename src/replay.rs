@@ -0,0 +1,147 @@
+//! Recording the events processed by state machines with `replay: true`, and replaying them
+//! with the recorded results of the guards.
+
+use core::future::Future;
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+use crate::{ProcessEvent, ProcessEventAsync};
+
+/// An event processed by a state machine, with the results of the guards evaluated while
+/// processing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoggedEvent<E> {
+    /// The event.
+    pub event: E,
+    /// The results of the guards, in the order they were called. Guards skipped by the short
+    /// circuit of `&&` and `||` are not called and not recorded, and neither is a guard which
+    /// returned an error.
+    pub guards: Vec<bool>,
+}
+
+/// The events processed by a state machine while recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EventLog<E> {
+    /// The events, from the oldest to the newest.
+    pub events: Vec<LoggedEvent<E>>,
+}
+
+impl<E> EventLog<E> {
+    /// Creates an empty log.
+    pub const fn new() -> Self {
+        EventLog { events: Vec::new() }
+    }
+
+    /// Returns an iterator over the events, from the oldest to the newest.
+    pub fn iter(&self) -> impl Iterator<Item = &LoggedEvent<E>> + '_ {
+        self.events.iter()
+    }
+
+    /// Returns the number of events.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns true if no events are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Records an event, used by the generated state machine.
+    pub fn record_event(&mut self, event: E) {
+        self.events.push(LoggedEvent {
+            event,
+            guards: Vec::new(),
+        });
+    }
+
+    /// Records the result of a guard of the last event, used by the generated state machine.
+    pub fn record_guard(&mut self, passed: bool) {
+        if let Some(logged) = self.events.last_mut() {
+            logged.guards.push(passed);
+        }
+    }
+}
+
+impl<E> Default for EventLog<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The recorded results of the guards which a state machine uses instead of calling its guards
+/// while replaying an event.
+///
+/// When the tape is empty, the guards of the context are called.
+#[derive(Debug, Clone, Default)]
+pub struct GuardTape {
+    guards: VecDeque<bool>,
+}
+
+impl GuardTape {
+    /// Creates an empty tape.
+    pub const fn new() -> Self {
+        GuardTape {
+            guards: VecDeque::new(),
+        }
+    }
+
+    /// Replaces the results on the tape.
+    pub fn load(&mut self, guards: &[bool]) {
+        self.guards.clear();
+        self.guards.extend(guards);
+    }
+
+    /// Takes the next result from the tape, if any.
+    pub fn next_guard(&mut self) -> Option<bool> {
+        self.guards.pop_front()
+    }
+
+    /// Removes all results from the tape.
+    pub fn clear(&mut self) {
+        self.guards.clear();
+    }
+}
+
+/// A state machine which replays logged events, implemented by every generated state machine
+/// with `replay: true` which is not `async` and implements [`ProcessEvent`].
+pub trait Replay: ProcessEvent {
+    /// Processes a logged event with the recorded results of its guards, see the `replay_event`
+    /// method of the generated state machine.
+    fn replay_event(&mut self, logged: &LoggedEvent<Self::Event>) -> Result<(), Self::Error>;
+}
+
+/// The `async` version of [`Replay`], implemented by every generated state machine with
+/// `replay: true` which is `async` and implements [`ProcessEventAsync`].
+pub trait ReplayAsync: ProcessEventAsync {
+    /// Processes a logged event with the recorded results of its guards, see the `replay_event`
+    /// method of the generated state machine.
+    fn replay_event(
+        &mut self,
+        logged: &LoggedEvent<Self::Event>,
+    ) -> impl Future<Output = Result<(), Self::Error>>;
+}
+
+/// Replays all events of a log, returning the result of every event.
+///
+/// Events which failed when they were recorded are expected to fail again, so replaying does not
+/// stop at an error.
+pub fn replay<M: Replay>(machine: &mut M, log: &EventLog<M::Event>) -> Vec<Result<(), M::Error>> {
+    log.iter()
+        .map(|logged| machine.replay_event(logged))
+        .collect()
+}
+
+/// The `async` version of [`replay`].
+pub async fn replay_async<M: ReplayAsync>(
+    machine: &mut M,
+    log: &EventLog<M::Event>,
+) -> Vec<Result<(), M::Error>> {
+    let mut results = Vec::with_capacity(log.len());
+    for logged in log.iter() {
+        results.push(machine.replay_event(logged).await);
+    }
+    results
+}
@@ -0,0 +1,345 @@
+//! Runners owning a state machine and a bounded queue of events, which process the queued events
+//! in order.
+//!
+//! A [`Mailbox`] has a fixed capacity and works on `no_std`. Events are queued through cloneable
+//! [`Sender`]s, for example from actions of the state machine itself. [`Runner::run`] and
+//! [`Runner::drain_async`] process the queued events and return, while [`Runner::run_async`]
+//! waits for new events forever, like an actor. The `_send` versions of the async methods return
+//! `Send` futures for state machines with `async_send: true`. With the `critical-section`
+//! feature, the mailbox can be stored in a `static` and fed from interrupt handlers and other
+//! tasks. With the `std` feature, [`ChannelRunner`] uses a `std::sync::mpsc` channel instead, so
+//! events can be sent from other threads.
+
+use crate::{ProcessEvent, ProcessEventAsync, ProcessEventSend};
+use core::cell::RefCell;
+use core::convert::Infallible;
+use core::fmt;
+use core::future::poll_fn;
+use core::task::{Poll, Waker};
+
+/// The error returned when sending an event to a full [`Mailbox`], containing the event.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Full<E>(pub E);
+
+impl<E> fmt::Debug for Full<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Full(..)")
+    }
+}
+
+impl<E> fmt::Display for Full<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("the mailbox is full")
+    }
+}
+
+// A ring buffer of events, and the waker of the task waiting for an event.
+struct Queue<E, const N: usize> {
+    slots: [Option<E>; N],
+    head: usize,
+    len: usize,
+    waker: Option<Waker>,
+}
+
+impl<E, const N: usize> Queue<E, N> {
+    fn pop(&mut self) -> Option<E> {
+        if self.len == 0 {
+            return None;
+        }
+        let head = self.head;
+        self.head = (head + 1) % N;
+        self.len -= 1;
+        self.slots[head].take()
+    }
+}
+
+#[cfg(not(feature = "critical-section"))]
+type QueueCell<E, const N: usize> = RefCell<Queue<E, N>>;
+#[cfg(feature = "critical-section")]
+type QueueCell<E, const N: usize> = critical_section::Mutex<RefCell<Queue<E, N>>>;
+
+/// A bounded queue of events with a fixed capacity of `N` events.
+///
+/// Without the `critical-section` feature, the mailbox can only be used from a single thread (or
+/// interrupt priority). With it, every access runs inside a critical section, so the mailbox is
+/// `Sync` for events which are `Send`, and can be stored in a `static` to queue events from
+/// interrupt handlers and other tasks.
+pub struct Mailbox<E, const N: usize> {
+    queue: QueueCell<E, N>,
+}
+
+impl<E, const N: usize> Mailbox<E, N> {
+    /// Creates an empty mailbox.
+    pub const fn new() -> Self {
+        let queue = RefCell::new(Queue {
+            slots: [const { None }; N],
+            head: 0,
+            len: 0,
+            waker: None,
+        });
+        Mailbox {
+            #[cfg(not(feature = "critical-section"))]
+            queue,
+            #[cfg(feature = "critical-section")]
+            queue: critical_section::Mutex::new(queue),
+        }
+    }
+
+    #[cfg(not(feature = "critical-section"))]
+    fn with_queue<R>(&self, f: impl FnOnce(&mut Queue<E, N>) -> R) -> R {
+        f(&mut self.queue.borrow_mut())
+    }
+
+    #[cfg(feature = "critical-section")]
+    fn with_queue<R>(&self, f: impl FnOnce(&mut Queue<E, N>) -> R) -> R {
+        critical_section::with(|cs| f(&mut self.queue.borrow_ref_mut(cs)))
+    }
+
+    /// Returns a sender which queues events in this mailbox.
+    pub fn sender(&self) -> Sender<'_, E, N> {
+        Sender { mailbox: self }
+    }
+
+    /// Queues an event and wakes the task waiting in [`Mailbox::receive_async`], if any. Returns
+    /// the event back if the mailbox is full.
+    pub fn send(&self, event: E) -> Result<(), Full<E>> {
+        let waker = self.with_queue(|queue| {
+            if queue.len == N {
+                return Err(Full(event));
+            }
+            let tail = (queue.head + queue.len) % N;
+            queue.slots[tail] = Some(event);
+            queue.len += 1;
+            Ok(queue.waker.take())
+        })?;
+        // The task is woken outside of the queue, as waking may run code of the executor
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Takes the oldest queued event, if any.
+    pub fn receive(&self) -> Option<E> {
+        self.with_queue(|queue| queue.pop())
+    }
+
+    /// Waits for an event and takes it. Only one task can wait at a time, a second waiting task
+    /// replaces the first one.
+    pub async fn receive_async(&self) -> E {
+        poll_fn(|cx| {
+            self.with_queue(|queue| match queue.pop() {
+                Some(event) => Poll::Ready(event),
+                None => {
+                    match &mut queue.waker {
+                        Some(waker) if waker.will_wake(cx.waker()) => {}
+                        waker => *waker = Some(cx.waker().clone()),
+                    }
+                    Poll::Pending
+                }
+            })
+        })
+        .await
+    }
+
+    /// Returns the number of queued events.
+    pub fn len(&self) -> usize {
+        self.with_queue(|queue| queue.len)
+    }
+
+    /// Returns true if no events are queued.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<E, const N: usize> Default for Mailbox<E, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Queues events in a [`Mailbox`].
+pub struct Sender<'a, E, const N: usize> {
+    mailbox: &'a Mailbox<E, N>,
+}
+
+impl<E, const N: usize> Sender<'_, E, N> {
+    /// Queues an event. Returns the event back if the mailbox is full.
+    pub fn send(&self, event: E) -> Result<(), Full<E>> {
+        self.mailbox.send(event)
+    }
+}
+
+impl<E, const N: usize> Clone for Sender<'_, E, N> {
+    fn clone(&self) -> Self {
+        Sender {
+            mailbox: self.mailbox,
+        }
+    }
+}
+
+/// Owns a state machine and processes the events queued in a [`Mailbox`].
+pub struct Runner<'a, M, E, const N: usize> {
+    machine: M,
+    mailbox: &'a Mailbox<E, N>,
+}
+
+impl<'a, M, E, const N: usize> Runner<'a, M, E, N> {
+    /// Creates a runner processing the events of `mailbox` with `machine`.
+    pub fn new(machine: M, mailbox: &'a Mailbox<E, N>) -> Self {
+        Runner { machine, mailbox }
+    }
+
+    /// Returns a sender which queues events for this runner.
+    pub fn sender(&self) -> Sender<'a, E, N> {
+        self.mailbox.sender()
+    }
+
+    /// Returns the state machine.
+    pub fn machine(&self) -> &M {
+        &self.machine
+    }
+
+    /// Returns the state machine as a mutable reference.
+    pub fn machine_mut(&mut self) -> &mut M {
+        &mut self.machine
+    }
+
+    /// Returns the state machine, dropping the runner.
+    pub fn into_inner(self) -> M {
+        self.machine
+    }
+}
+
+impl<M: ProcessEvent<Event = E>, E, const N: usize> Runner<'_, M, E, N> {
+    /// Processes the queued events in order until the mailbox is empty, including events queued
+    /// while processing. Errors are passed to `on_error`, after which the next event is processed.
+    ///
+    /// Returns the number of processed events.
+    pub fn run(&mut self, mut on_error: impl FnMut(M::Error)) -> usize {
+        let mut processed = 0;
+        while let Some(event) = self.mailbox.receive() {
+            if let Err(error) = self.machine.process_event(event) {
+                on_error(error);
+            }
+            processed += 1;
+        }
+        processed
+    }
+}
+
+impl<M: ProcessEventAsync<Event = E>, E, const N: usize> Runner<'_, M, E, N> {
+    /// Waits for events and processes them in order, forever. Errors are passed to `on_error`,
+    /// after which the next event is processed. The future never completes, drop it to stop the
+    /// runner.
+    pub async fn run_async(&mut self, mut on_error: impl FnMut(M::Error)) -> Infallible {
+        loop {
+            let event = self.mailbox.receive_async().await;
+            if let Err(error) = self.machine.process_event(event).await {
+                on_error(error);
+            }
+        }
+    }
+
+    /// The `async` version of [`Runner::run`], which processes the queued events and returns
+    /// when the mailbox is empty instead of waiting for new events.
+    ///
+    /// Returns the number of processed events.
+    pub async fn drain_async(&mut self, mut on_error: impl FnMut(M::Error)) -> usize {
+        let mut processed = 0;
+        while let Some(event) = self.mailbox.receive() {
+            if let Err(error) = self.machine.process_event(event).await {
+                on_error(error);
+            }
+            processed += 1;
+        }
+        processed
+    }
+}
+
+impl<M: ProcessEventSend<Event = E>, E, const N: usize> Runner<'_, M, E, N> {
+    /// The `Send` version of [`Runner::run_async`], for state machines with `async_send: true`.
+    /// With the `critical-section` feature, the future can be spawned on a multi-threaded
+    /// executor.
+    pub async fn run_async_send(
+        &mut self,
+        mut on_error: impl FnMut(M::Error) + Send,
+    ) -> Infallible {
+        loop {
+            let event = self.mailbox.receive_async().await;
+            if let Err(error) = ProcessEventSend::process_event(&mut self.machine, event).await {
+                on_error(error);
+            }
+        }
+    }
+
+    /// The `Send` version of [`Runner::drain_async`], for state machines with `async_send: true`.
+    ///
+    /// Returns the number of processed events.
+    pub async fn drain_async_send(&mut self, mut on_error: impl FnMut(M::Error) + Send) -> usize {
+        let mut processed = 0;
+        while let Some(event) = self.mailbox.receive() {
+            if let Err(error) = ProcessEventSend::process_event(&mut self.machine, event).await {
+                on_error(error);
+            }
+            processed += 1;
+        }
+        processed
+    }
+}
+
+#[cfg(feature = "std")]
+pub use self::channel::ChannelRunner;
+
+#[cfg(feature = "std")]
+mod channel {
+    use crate::ProcessEvent;
+    use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+
+    /// Owns a state machine and processes the events sent through a bounded `std::sync::mpsc`
+    /// channel, which can be used from other threads.
+    pub struct ChannelRunner<M: ProcessEvent> {
+        machine: M,
+        receiver: Receiver<M::Event>,
+    }
+
+    impl<M: ProcessEvent> ChannelRunner<M> {
+        /// Creates a runner and the sender of its channel, which can hold up to `capacity`
+        /// events. The sender can be cloned.
+        pub fn new(machine: M, capacity: usize) -> (Self, SyncSender<M::Event>) {
+            let (sender, receiver) = sync_channel(capacity);
+            (ChannelRunner { machine, receiver }, sender)
+        }
+
+        /// Processes the events in order until all senders have been dropped. Errors are passed
+        /// to `on_error`, after which the next event is processed.
+        ///
+        /// Returns the number of processed events.
+        pub fn run(&mut self, mut on_error: impl FnMut(M::Error)) -> usize {
+            let mut processed = 0;
+            while let Ok(event) = self.receiver.recv() {
+                if let Err(error) = self.machine.process_event(event) {
+                    on_error(error);
+                }
+                processed += 1;
+            }
+            processed
+        }
+
+        /// Returns the state machine.
+        pub fn machine(&self) -> &M {
+            &self.machine
+        }
+
+        /// Returns the state machine as a mutable reference.
+        pub fn machine_mut(&mut self) -> &mut M {
+            &mut self.machine
+        }
+
+        /// Returns the state machine, dropping the runner.
+        pub fn into_inner(self) -> M {
+            self.machine
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! A state machine which can be shared between interrupt handlers and threads.
+
+use crate::ProcessEvent;
+use core::cell::RefCell;
+use critical_section::Mutex;
+
+/// Wraps a state machine to process events through a shared reference, by running each call
+/// inside a critical section.
+///
+/// With its `const fn new`, the state machine can be stored in a `static` and used from several
+/// interrupt handlers without `static mut`:
+///
+/// ```ignore
+/// static SM: SharedStateMachine<StateMachine<Context>> =
+///     SharedStateMachine::new(StateMachine::new(Context));
+///
+/// #[interrupt]
+/// fn UART0() {
+///     SM.process_event(Events::Received).ok();
+/// }
+/// ```
+///
+/// Interrupts are disabled while an event is processed, so guards, actions and entry and exit
+/// functions should be short.
+pub struct SharedStateMachine<M> {
+    machine: Mutex<RefCell<M>>,
+}
+
+impl<M> SharedStateMachine<M> {
+    /// Wraps a state machine.
+    pub const fn new(machine: M) -> Self {
+        SharedStateMachine {
+            machine: Mutex::new(RefCell::new(machine)),
+        }
+    }
+
+    /// Runs `f` with the state machine inside a critical section, for example to read the state
+    /// or to access the context.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from within `f`, or from a guard or action of the state machine.
+    pub fn with<R>(&self, f: impl FnOnce(&mut M) -> R) -> R {
+        critical_section::with(|cs| f(&mut self.machine.borrow_ref_mut(cs)))
+    }
+
+    /// Returns the state machine, dropping the wrapper.
+    pub fn into_inner(self) -> M {
+        self.machine.into_inner().into_inner()
+    }
+}
+
+impl<M: ProcessEvent> SharedStateMachine<M> {
+    /// Processes an event inside a critical section, see the `process_event` method of the
+    /// generated state machine.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a guard or action of the state machine.
+    pub fn process_event(&self, event: M::Event) -> Result<(), M::Error> {
+        self.with(|machine| machine.process_event(event))
+    }
+}
@@ -0,0 +1,51 @@
+use core::fmt;
+
+/// Static description of a transition, as written in the `statemachine!` definition.
+///
+/// Every generated state machine exposes a `TRANSITIONS` table with one entry per transition
+/// (after expanding wildcards and input state patterns).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitionInfo {
+    /// The state the transition starts in.
+    pub from: &'static str,
+    /// The event triggering the transition.
+    pub event: &'static str,
+    /// The guard expression, if the transition is guarded.
+    pub guard: Option<&'static str>,
+    /// The action run during the transition, if any.
+    pub action: Option<&'static str>,
+    /// The state the transition ends in.
+    pub to: &'static str,
+}
+
+impl fmt::Display for TransitionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} + {}", self.from, self.event)?;
+        if let Some(guard) = self.guard {
+            write!(f, " [{}]", guard)?;
+        }
+        if let Some(action) = self.action {
+            write!(f, " / {}", action)?;
+        }
+        write!(f, " = {}", self.to)
+    }
+}
+
+/// A transition taken by a state machine, passed to the `on_transition` function of the context
+/// of state machines with `on_transition: true`.
+#[derive(Debug)]
+pub struct TransitionRecord<'a, S, E> {
+    /// The state the transition starts in.
+    pub from: &'a S,
+    /// The state the transition ends in.
+    pub to: &'a S,
+    /// The event triggering the transition.
+    pub event: &'a E,
+    /// The guard expression, if the transition is guarded.
+    pub guard: Option<&'static str>,
+    /// The action run during the transition, if any.
+    pub action: Option<&'static str>,
+    /// True if the state machine stays in the same state, without calling the entry and exit
+    /// functions.
+    pub internal: bool,
+}
@@ -0,0 +1,106 @@
+//! Driving state machines with sequences of events while checking invariants, for example with
+//! events generated by `cargo fuzz` or `proptest`.
+//!
+//! With the `arbitrary` and `proptest` features, the `Events` and `StateKind` of every generated
+//! state machine whose events do not borrow data implement the `Arbitrary` trait of the crate.
+
+use core::fmt;
+
+use crate::{ProcessEvent, ProcessEventAsync};
+
+/// An invariant which did not hold after processing an event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation<E> {
+    /// The number of the step, starting at 0.
+    pub step: usize,
+    /// The error returned by the invariant.
+    pub error: E,
+}
+
+impl<E: fmt::Display> fmt::Display for Violation<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invariant violated at step {}: {}",
+            self.step, self.error
+        )
+    }
+}
+
+/// Processes the events one after another, and checks the invariant after every step.
+///
+/// The invariant receives the state machine and the result of processing the event, so it can
+/// decide which errors are expected. The walk stops at the first violation.
+pub fn walk<M, E>(
+    machine: &mut M,
+    events: impl IntoIterator<Item = M::Event>,
+    mut invariant: impl FnMut(&M, &Result<(), M::Error>) -> Result<(), E>,
+) -> Result<(), Violation<E>>
+where
+    M: ProcessEvent,
+{
+    for (step, event) in events.into_iter().enumerate() {
+        let result = machine.process_event(event);
+        invariant(machine, &result).map_err(|error| Violation { step, error })?;
+    }
+    Ok(())
+}
+
+/// The `async` version of [`walk`].
+pub async fn walk_async<M, E>(
+    machine: &mut M,
+    events: impl IntoIterator<Item = M::Event>,
+    mut invariant: impl FnMut(&M, &Result<(), M::Error>) -> Result<(), E>,
+) -> Result<(), Violation<E>>
+where
+    M: ProcessEventAsync,
+{
+    for (step, event) in events.into_iter().enumerate() {
+        let result = machine.process_event(event).await;
+        invariant(machine, &result).map_err(|error| Violation { step, error })?;
+    }
+    Ok(())
+}
+
+/// Processes events generated from the unstructured data until it is used up, and checks the
+/// invariant after every step, see [`walk`].
+///
+/// This is the body of a fuzz target:
+///
+/// ```ignore
+/// fuzz_target!(|data: &[u8]| {
+///     let mut sm = StateMachine::new(Context::default());
+///     let mut u = Unstructured::new(data);
+///     smlang::walk::random_walk(&mut sm, &mut u, |sm, _| check(sm)).unwrap();
+/// });
+/// ```
+#[cfg(feature = "arbitrary")]
+pub fn random_walk<'a, M, E>(
+    machine: &mut M,
+    u: &mut arbitrary::Unstructured<'a>,
+    invariant: impl FnMut(&M, &Result<(), M::Error>) -> Result<(), E>,
+) -> Result<(), Violation<E>>
+where
+    M: ProcessEvent,
+    M::Event: arbitrary::Arbitrary<'a>,
+{
+    let events = core::iter::from_fn(|| {
+        if u.is_empty() {
+            return None;
+        }
+        arbitrary::Arbitrary::arbitrary(u).ok()
+    });
+    walk(machine, events, invariant)
+}
+
+/// Returns a strategy generating sequences of events with a length in the range, to [`walk`]
+/// through a state machine in a `proptest`.
+#[cfg(feature = "proptest")]
+pub fn event_sequences<E>(
+    length: impl Into<proptest::collection::SizeRange>,
+) -> impl proptest::strategy::Strategy<Value = std::vec::Vec<E>>
+where
+    E: proptest::arbitrary::Arbitrary,
+{
+    proptest::collection::vec(proptest::arbitrary::any::<E>(), length)
+}
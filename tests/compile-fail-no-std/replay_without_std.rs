@@ -0,0 +1,12 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    replay: true, //~ `replay: true` requires the `std` feature of smlang
+    transitions: {
+        *Idle + Start = Running,
+    }
+}
+
+fn main() {}
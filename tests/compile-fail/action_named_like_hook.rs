@@ -0,0 +1,10 @@
+use smlang::statemachine;
+
+statemachine! {
+    transitions: {
+        *Idle + Start / on_entry_running = Running,
+        Running + Stop = Idle,
+    }
+}
+
+fn main() {}
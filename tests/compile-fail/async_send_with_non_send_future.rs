@@ -0,0 +1,23 @@
+extern crate smlang;
+
+use smlang::statemachine;
+use std::rc::Rc;
+
+statemachine! {
+    async_send: true,
+    transitions: {
+        *State1 + Event1 [async guard1] = State2,
+    }
+}
+
+struct Context;
+
+impl StateMachineContext for Context {
+    async fn guard1(&self) -> Result<bool, ()> { //~ future cannot be sent between threads safely
+        let rc = Rc::new(true);
+        core::future::ready(()).await;
+        Ok(*rc)
+    }
+}
+
+fn main() {}
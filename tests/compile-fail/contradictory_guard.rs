@@ -0,0 +1,12 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    transitions: {
+        *State1 + Event1 [a && !a] = State2, //~ guard can never be true, so the transition is unreachable
+        State1 + Event2 = State2,
+    }
+}
+
+fn main() {}
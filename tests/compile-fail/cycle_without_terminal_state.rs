@@ -0,0 +1,15 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    terminal_states: [Off],
+    transitions: {
+        *Idle + Start = Running,
+        Idle + Shutdown = Off,
+        Running + Pause = Paused, //~ No terminal state can be reached from state `Paused`
+        Paused + Resume = Running, //~ No terminal state can be reached from state `Running`
+    }
+}
+
+fn main() {}
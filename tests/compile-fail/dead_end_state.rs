@@ -0,0 +1,13 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    terminal_states: [Off],
+    transitions: {
+        *Running + Fault = Recovering, //~ State `Recovering` has no outgoing transitions
+        Running + Shutdown = Off,
+    }
+}
+
+fn main() {}
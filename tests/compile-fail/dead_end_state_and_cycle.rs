@@ -0,0 +1,16 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    terminal_states: [Off],
+    transitions: {
+        *Idle + Start = Running,
+        Idle + Fault = Recovering, //~ State `Recovering` has no outgoing transitions
+        Idle + Shutdown = Off,
+        Running + Pause = Paused, //~ No terminal state can be reached from state `Paused`
+        Paused + Resume = Running, //~ No terminal state can be reached from state `Running`
+    }
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    transitions: {
+        *State1 + Event1 [a && b] = State2,
+        State1 + Event1 [b && a] = State3, //~ transition is unreachable because an earlier transition has an equivalent guard
+    }
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    transitions: {
+        *State1 + Event1 [a || !a] = State2,
+        State1 + Event1 = State3, //~ transition is unreachable because an earlier guard is always true
+    }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use smlang::statemachine;
+
+statemachine! {
+    history_timestamp: u64,
+    transitions: {
+        *Idle + Start = Running,
+        Running + Stop = Idle,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+#![allow(non_camel_case_types)]
+
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    transitions: {
+        *FooBar + Event1 / enter = Foo_Bar(u32),
+        Foo_Bar(u32) + Event1 = FooBar,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    transitions: {
+        *Idle + Start = Running,
+        Running + Start / restart = ignore, //~ Ignored events cannot have an action.
+    }
+}
+
+fn main() {}
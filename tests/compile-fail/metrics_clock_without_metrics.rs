@@ -0,0 +1,11 @@
+use smlang::statemachine;
+
+statemachine! {
+    metrics_clock: true,
+    transitions: {
+        *Idle + Start = Running,
+        Running + Stop = Idle,
+    }
+}
+
+fn main() {}
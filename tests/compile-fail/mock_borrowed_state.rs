@@ -0,0 +1,11 @@
+use smlang::statemachine;
+
+statemachine! {
+    mock: true,
+    transitions: {
+        *Idle + Start / start = Running(&'a [u8]),
+        Running(&'a [u8]) + Stop = Idle,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    exhaustive: true,
+    transitions: {
+        *Idle + Start = Running, //~ State `Idle` does not handle event `Stop`
+        Running + Stop = Idle, //~ State `Running` does not handle event `Start`
+    }
+}
+
+fn main() {}
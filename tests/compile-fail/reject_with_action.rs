@@ -0,0 +1,12 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    transitions: {
+        *State1 + Event1 = State2,
+        State2 + Event1 / action = reject(NotAllowed), //~ Rejected events cannot have an action.
+    }
+}
+
+fn main() {}
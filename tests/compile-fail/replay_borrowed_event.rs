@@ -0,0 +1,11 @@
+use smlang::statemachine;
+
+statemachine! {
+    replay: true,
+    transitions: {
+        *Idle + Start(&'a [u8]) / start = Running,
+        Running + Stop = Idle,
+    }
+}
+
+fn main() {}
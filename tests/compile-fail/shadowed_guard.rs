@@ -0,0 +1,13 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    transitions: {
+        *State1 + Event1 [a] = State2,
+        State1 + Event1 [b] = State3,
+        State1 + Event1 [a || b] = State4, //~ transition is unreachable because the earlier guards handle all cases in which it is true
+    }
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    lints: { unreachable_states: warn }, //~ Unknown lint `unreachable_states`
+    transitions: {
+        *State1 + Event1 = State2,
+    }
+}
+
+fn main() {}
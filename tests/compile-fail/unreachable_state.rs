@@ -0,0 +1,12 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    transitions: {
+        *Idle + Start = Runing,
+        Running + Stop = Idle, //~ State `Running` is unreachable from the starting state
+    }
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+extern crate smlang;
+
+use smlang::statemachine;
+
+statemachine! {
+    transitions: {
+        *State1 + Event1 / to_state2 = State2(u32),
+        State2(u32) + Event2 = State1,
+        _ + Event3, //~ This state has data associated, but not action is define here to provide it.
+    }
+}
+
+fn main() {}
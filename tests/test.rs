@@ -43,6 +43,13 @@ fn compile_fail_tests() {
     let t = trybuild::TestCases::new();
     t.compile_fail("tests/compile-fail/*.rs");
 }
+
+#[cfg(not(feature = "std"))]
+#[test]
+fn compile_fail_no_std_tests() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/compile-fail-no-std/*.rs");
+}
 #[test]
 fn wildcard_after_input_state() {
     statemachine! {
@@ -71,6 +78,7 @@ fn multiple_lifetimes() {
     pub struct Z;
 
     statemachine! {
+        entry_states: [State4],
         transitions: {
             *State1 + Event1(&'a X) [guard1] / action1 = State2(&'a X),
             State2(&'a X) + Event2(&'b Y) [guard2] / action2 = State3((&'a X, &'b Y)),
@@ -200,320 +208,1861 @@ fn async_guards_and_actions() {
 }
 
 #[test]
-fn guard_expressions() {
-    #[derive(PartialEq, Display)]
-    pub struct Entry(pub u32);
-
+fn async_send_futures_can_be_spawned() {
     statemachine! {
-        states_attr: #[derive(Display, Debug)],
+        async_send: true,
         transitions: {
-            *Init + Login(&'a Entry) [valid_entry] / attempt = LoggedIn,
-            Init + Login(&'a Entry) [!valid_entry && !too_many_attempts] / attempt = Init,
-            Init + Login(&'a Entry) [!valid_entry && too_many_attempts] / attempt = LoginDenied,
-            LoggedIn + Logout / reset = Init,
+            *State1 + Event1 [async guard1] / async action1 = State2(u32),
+            State2(u32) + Event2 [guard2] = State1,
         }
     }
 
-    /// Context
-    pub struct Context {
-        password: u32,
-        attempts: u32,
+    struct Context {
+        count: u32,
     }
+
     impl StateMachineContext for Context {
-        fn valid_entry(&self, e: &Entry) -> Result<bool, ()> {
-            Ok(e.0 == self.password)
-        }
-        fn too_many_attempts(&self, _e: &Entry) -> Result<bool, ()> {
-            Ok(self.attempts >= 3)
+        async fn guard1(&self) -> Result<bool, ()> {
+            smol::future::yield_now().await;
+            Ok(true)
         }
-        fn reset(&mut self) -> Result<(), ()> {
-            self.attempts = 0;
-            Ok(())
+
+        async fn action1(&mut self) -> Result<u32, ()> {
+            smol::future::yield_now().await;
+            self.count += 1;
+            Ok(self.count)
         }
-        fn attempt(&mut self, _e: &Entry) -> Result<(), ()> {
-            self.attempts += 1;
-            Ok(())
+
+        fn guard2(&self, _state_data: &u32) -> Result<bool, ()> {
+            Ok(true)
         }
     }
 
-    let mut sm = StateMachine::new(Context {
-        password: 42,
-        attempts: 0,
+    fn assert_send<F: Send>(future: F) -> F {
+        future
+    }
+
+    // The task driving the state machine runs on a multi-threaded executor
+    let task = smol::spawn(async move {
+        let mut sm = StateMachine::new(Context { count: 0 });
+        assert_send(sm.process_event(Events::Event1)).await.unwrap();
+        sm.process_event(Events::Event2).await.unwrap();
+        sm.process_event(Events::Event1).await.unwrap();
+        sm
     });
-    assert!(matches!(sm.state(), &States::Init));
 
-    let bad_entry = Entry(10);
-    let good_entry = Entry(42);
+    let sm = smol::block_on(task);
+    assert!(matches!(sm.state(), &States::State2(2)));
+}
 
-    let _ = sm.process_event(Events::Login(&bad_entry));
-    assert_eq!(sm.context().attempts, 1);
-    assert!(matches!(sm.state(), &States::Init));
+#[test]
+fn async_send_hooks_can_be_spawned() {
+    statemachine! {
+        async_send: true,
+        async_hooks: true,
+        transitions: {
+            *Idle + Start = Running,
+            Running + Stop = Idle,
+        }
+    }
 
-    let _ = sm.process_event(Events::Login(&bad_entry));
-    assert_eq!(sm.context().attempts, 2);
-    assert!(matches!(sm.state(), &States::Init));
+    struct Context {
+        runs: u32,
+    }
 
-    let _ = sm.process_event(Events::Login(&good_entry));
-    assert_eq!(sm.context().attempts, 3);
-    assert!(matches!(sm.state(), &States::LoggedIn));
+    impl StateMachineContext for Context {
+        async fn on_entry_running(&mut self) -> Result<(), ()> {
+            smol::future::yield_now().await;
+            self.runs += 1;
+            Ok(())
+        }
 
-    let _ = sm.process_event(Events::Logout);
-    assert_eq!(sm.context().attempts, 0);
-    assert!(matches!(sm.state(), &States::Init));
+        async fn on_exit_running(&mut self) -> Result<(), ()> {
+            smol::future::yield_now().await;
+            Ok(())
+        }
+    }
 
-    let _ = sm.process_event(Events::Login(&bad_entry));
-    let _ = sm.process_event(Events::Login(&bad_entry));
-    let _ = sm.process_event(Events::Login(&bad_entry));
-    assert_eq!(sm.context().attempts, 3);
-    assert!(matches!(sm.state(), &States::Init));
+    fn assert_send<F: Send>(future: F) -> F {
+        future
+    }
 
-    // exhausted attempts
-    let _ = sm.process_event(Events::Login(&bad_entry));
-    assert_eq!(sm.context().attempts, 4);
-    assert!(matches!(sm.state(), &States::LoginDenied));
+    let task = smol::spawn(async move {
+        let mut sm = StateMachine::new(Context { runs: 0 });
+        assert_send(sm.process_event(Events::Start)).await.unwrap();
+        sm.process_event(Events::Stop).await.unwrap();
+        sm.process_event(Events::Start).await.unwrap();
+        sm
+    });
 
-    // Invalid event, as we are in a final state
-    assert_eq!(
-        sm.process_event(Events::Login(&good_entry)),
-        Err(Error::InvalidEvent)
-    );
-    assert_eq!(sm.context().attempts, 4);
-    assert!(matches!(sm.state(), &States::LoginDenied));
+    let sm = smol::block_on(task);
+    assert!(matches!(sm.state(), &States::Running));
+    assert_eq!(sm.context().runs, 2);
 }
+
 #[test]
-fn guarded_transition_before_unguarded() {
-    use smlang::statemachine;
+fn async_entry_exit_hooks() {
     statemachine! {
+        async_hooks: true,
+        custom_error: true,
+        states_attr: #[derive(Debug)],
         transitions: {
-            *State1 + Event1 [guard] / disable = State2,
-            State1 + Event1 = Fault,
-            State2 + Event1 = State1,
+            *Disconnected + Connect / async connect = Connected,
+            Connected + Disconnect = Disconnected,
         }
     }
+
+    #[derive(Default)]
     struct Context {
-        pub enabled: bool,
+        calls: Vec<&'static str>,
+        link_up: bool,
     }
+
     impl StateMachineContext for Context {
-        fn guard(&self) -> Result<bool, ()> {
-            Ok(self.enabled)
+        type Error = &'static str;
+
+        async fn connect(&mut self) -> Result<(), Self::Error> {
+            self.calls.push("connect");
+            Ok(())
         }
 
-        fn disable(&mut self) -> Result<(), ()> {
-            self.enabled = false;
+        async fn on_exit_disconnected(&mut self) -> Result<(), Self::Error> {
+            smol::future::yield_now().await;
+            self.calls.push("exit disconnected");
             Ok(())
         }
-    }
-    let mut sm = StateMachine::new(Context { enabled: true });
-    sm.process_event(Events::Event1).unwrap();
-    assert!(matches!(sm.state(), &States::State2));
 
-    sm.process_event(Events::Event1).unwrap();
-    assert!(matches!(sm.state(), &States::State1));
+        async fn on_entry_connected(&mut self) -> Result<(), Self::Error> {
+            smol::future::yield_now().await;
+            self.calls.push("send hello");
+            self.link_up = true;
+            Ok(())
+        }
 
-    sm.process_event(Events::Event1).unwrap();
-    assert!(matches!(sm.state(), &States::Fault));
+        async fn on_exit_connected(&mut self) -> Result<(), Self::Error> {
+            if self.link_up {
+                Err("link is up")
+            } else {
+                Ok(())
+            }
+        }
+
+        async fn transition_callback(&self, _: &States, _: &States) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    smol::block_on(async {
+        let mut sm = StateMachine::new(Context::default());
+        sm.process_event(Events::Connect).await.unwrap();
+        assert!(matches!(sm.state(), &States::Connected));
+        assert_eq!(
+            sm.context().calls,
+            ["exit disconnected", "connect", "send hello"]
+        );
+
+        // A failing exit function keeps the current state
+        assert_eq!(
+            sm.process_event(Events::Disconnect).await,
+            Err(Error::ExitFailed("link is up"))
+        );
+        assert!(matches!(sm.state(), &States::Connected));
+
+        sm.context_mut().link_up = false;
+        sm.process_event(Events::Disconnect).await.unwrap();
+        assert!(matches!(sm.state(), &States::Disconnected));
+    });
 }
 
 #[test]
-fn guard_errors() {
-    use smlang::statemachine;
+fn entry_exit_with_state_data_and_event() {
     statemachine! {
+        entry_exit_event: true,
+        events_attr: #[derive(Debug)],
         transitions: {
-            *Init + Event1 [guard] = Done,
+            *Stopped + Start(u32) / start = Running(u32),
+            Running(u32) + Stop = Stopped,
         }
     }
 
+    #[derive(Default)]
     struct Context {
-        pub guard_passable: bool,
-        pub guard_errors: bool,
+        calls: Vec<String>,
     }
+
     impl StateMachineContext for Context {
-        fn guard(&self) -> Result<bool, ()> {
-            if self.guard_errors {
-                Err(())
-            } else {
-                Ok(self.guard_passable)
-            }
+        fn start(&mut self, speed: u32) -> Result<u32, ()> {
+            Ok(speed)
         }
-    }
-
-    let mut sm = StateMachine::new(Context {
-        guard_passable: false,
-        guard_errors: true,
-    });
 
-    // Test attempting to transition when the guard fails.
-    sm.context_mut().guard_errors = true;
-    assert!(sm.process_event(Events::Event1).is_err());
-    assert!(matches!(sm.state(), &States::Init));
+        fn on_exit_stopped(&mut self, event: &Events) {
+            self.calls.push(format!("exit stopped on {:?}", event));
+        }
 
-    // Test attempting to transition when the guard is not passable.
-    sm.context_mut().guard_errors = false;
-    assert!(sm.process_event(Events::Event1).is_err());
-    assert!(matches!(sm.state(), &States::Init));
+        fn on_entry_running(&mut self, speed: &u32, event: &Events) {
+            self.calls
+                .push(format!("enter running at {} on {:?}", speed, event));
+        }
 
-    assert!(sm.process_event(Events::Event1).is_err());
-    assert!(matches!(sm.state(), &States::Init));
+        fn on_exit_running(&mut self, speed: &u32, event: &Events) {
+            self.calls
+                .push(format!("exit running at {} on {:?}", speed, event));
+        }
+    }
 
-    sm.context_mut().guard_passable = true;
-    sm.process_event(Events::Event1).unwrap();
-    assert!(matches!(sm.state(), &States::Done));
+    let mut sm = StateMachine::new(Context::default());
+    sm.process_event(Events::Start(3)).unwrap();
+    sm.process_event(Events::Stop).unwrap();
+    assert_eq!(
+        sm.context().calls,
+        [
+            "exit stopped on Start(3)",
+            "enter running at 3 on Start(3)",
+            "exit running at 3 on Stop",
+        ]
+    );
 }
+
 #[test]
-fn test_internal_transition_with_data() {
-    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-    pub struct State1Data(pub ActionId);
-    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-    pub struct State3Data(pub ActionId);
+fn event_data_is_moved_into_actions() {
+    // Without `entry_exit_event` and `on_transition`, the event data needs no `Clone`
+    pub struct Payload(Vec<u8>);
 
     statemachine! {
         transitions: {
-            *State1(State1Data) + Event2 / action12 = State2,
-            State1(State1Data) + Event3 / action13 = State3(State3Data),
-            State1(State1Data) + Event4 / action14 = State4(State3Data),
+            *Idle + Receive(Payload) / store = Storing(usize),
+            Storing(usize) + Done = Idle,
+        }
+    }
 
-            State2 + Event3 / action23 = State3(State3Data),
-            State4(State3Data) + Event1 / action44 = _, // Same as State4(State3Data) + Event1 / action44
+    #[derive(Default)]
+    struct Context {
+        stored: Vec<u8>,
+        entered: Vec<usize>,
+    }
 
-            // TRANSITION : _ + Event3 / increment_count = _, IS EQUIVALENT TO THE FOLLOWING TWO:
-            // State3(State3Data) + Event3 / action_3 = State3(State3Data),
-            // State4(State3Data) + Event3 / action_3 = State4(State3Data),
-            _ + Event3 / action_3 = _,
-        },
-        states_attr: #[derive(Debug, Clone,  Copy, Eq)]
+    impl StateMachineContext for Context {
+        fn store(&mut self, payload: Payload) -> Result<usize, ()> {
+            self.stored = payload.0;
+            Ok(self.stored.len())
+        }
+
+        fn on_entry_storing(&mut self, len: &usize) {
+            self.entered.push(*len);
+        }
     }
-    /// Context
-    #[derive(Default, Debug, PartialEq, Eq)]
-    pub struct Context {
-        action: ActionId,
+
+    let mut sm = StateMachine::new(Context::default());
+    sm.process_event(Events::Receive(Payload(vec![1, 2, 3])))
+        .unwrap();
+    assert_eq!(sm.context().stored, [1, 2, 3]);
+    assert_eq!(sm.context().entered, [3]);
+    sm.process_event(Events::Done).unwrap();
+    assert!(matches!(sm.state(), &States::Idle));
+}
+
+#[test]
+fn on_transition_records() {
+    use smlang::TransitionRecord;
+
+    statemachine! {
+        on_transition: true,
+        states_attr: #[derive(Debug)],
+        events_attr: #[derive(Debug)],
+        transitions: {
+            *Idle + Start(u32) [valid] / start = Running(u32),
+            Running(u32) + Start(u32) / restart = Running(u32),
+            Running(u32) + Stop = Idle,
+            Idle + Tick = _,
+        }
     }
-    #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
-    enum ActionId {
-        #[default]
-        Init,
-        Action3,
-        Action12,
-        Action13,
-        Action14,
-        Action23,
-        Action44,
+
+    #[derive(Default)]
+    struct Context {
+        records: Vec<String>,
     }
+
     impl StateMachineContext for Context {
-        fn action_3(&mut self, d: &State3Data) -> Result<State3Data, ()> {
-            self.action = ActionId::Action3;
-            Ok(*d)
+        fn valid(&self, speed: &u32) -> Result<bool, ()> {
+            Ok(*speed > 0)
         }
 
-        fn action44(&mut self, _d: &State3Data) -> Result<State3Data, ()> {
-            self.action = ActionId::Action44;
-            Ok(State3Data(ActionId::Action44))
+        fn start(&mut self, speed: u32) -> Result<u32, ()> {
+            Ok(speed)
         }
-        fn action14(&mut self, _d: &State1Data) -> Result<State3Data, ()> {
-            self.action = ActionId::Action14;
-            Ok(State3Data(ActionId::Action14))
+
+        fn restart(&mut self, _: &u32, speed: u32) -> Result<u32, ()> {
+            Ok(speed)
         }
-        fn action12(&mut self, _d: &State1Data) -> Result<(), ()> {
-            self.action = ActionId::Action12;
-            Ok(())
+
+        fn on_transition(&mut self, transition: &TransitionRecord<States, Events>) {
+            self.records.push(format!(
+                "{:?} + {:?} [{:?}] / {:?} = {:?}, internal: {}",
+                transition.from,
+                transition.event,
+                transition.guard,
+                transition.action,
+                transition.to,
+                transition.internal
+            ));
         }
-        fn action13(&mut self, _d: &State1Data) -> Result<State3Data, ()> {
-            self.action = ActionId::Action13;
-            Ok(State3Data(ActionId::Action13))
+    }
+
+    let mut sm = StateMachine::new(Context::default());
+    sm.process_event(Events::Start(1)).unwrap();
+    sm.process_event(Events::Start(2)).unwrap();
+    sm.process_event(Events::Stop).unwrap();
+    sm.process_event(Events::Tick).unwrap();
+    assert_eq!(
+        sm.context().records,
+        [
+            "Idle + Start(1) [Some(\"valid\")] / Some(\"start\") = Running(1), internal: false",
+            "Running(1) + Start(2) [None] / Some(\"restart\") = Running(2), internal: true",
+            "Running(2) + Stop [None] / None = Idle, internal: false",
+            "Idle + Tick [None] / None = Idle, internal: true",
+        ]
+    );
+}
+
+#[test]
+fn transition_history() {
+    use smlang::history::Outcome;
+
+    statemachine! {
+        history: 3,
+        history_timestamp: u64,
+        transitions: {
+            *Idle + Start [ready] = Running,
+            Running + Tick = _,
+            Running + Stop = Idle,
         }
-        fn action23(&mut self) -> Result<State3Data, ()> {
-            self.action = ActionId::Action23;
-            Ok(State3Data(ActionId::Action23))
+    }
+
+    #[derive(Default)]
+    struct Context {
+        now: u64,
+    }
+
+    impl StateMachineContext for Context {
+        fn ready(&self) -> Result<bool, ()> {
+            Ok(self.now > 1)
+        }
+
+        fn history_timestamp(&self) -> u64 {
+            self.now
         }
     }
 
-    {
-        let mut sm = StateMachine::new(Context::default(), State1Data(ActionId::Init));
-        matches!(States::State2, States::State2);
-        assert_transition_ok!(sm, Events::Event2, ActionId::Action12, States::State2);
-        assert!(sm.process_event(Events::Event1).is_err());
-        assert!(sm.process_event(Events::Event2).is_err());
-        assert!(sm.process_event(Events::Event4).is_err());
-        assert_transition_ok!(sm, Events::Event3, ActionId::Action23, States::State3(_));
-        assert_transition_ok!(sm, Events::Event3, ActionId::Action3, States::State3(_));
-        assert_transition_ok!(sm, Events::Event3, ActionId::Action3, States::State3(_));
-        assert!(sm.process_event(Events::Event1).is_err());
-        assert!(sm.process_event(Events::Event2).is_err());
-        assert!(sm.process_event(Events::Event4).is_err());
+    let mut sm = StateMachine::new(Context::default());
+    assert!(sm.history().is_empty());
+
+    for event in [Events::Start, Events::Stop, Events::Start, Events::Tick] {
+        sm.context_mut().now += 1;
+        let _ = sm.process_event(event);
     }
-    {
-        let mut sm = StateMachine::new(Context::default(), State1Data(ActionId::Init));
-        assert_transition_ok!(sm, Events::Event3, ActionId::Action13, States::State3(_));
-        assert!(sm.process_event(Events::Event1).is_err());
-        assert!(sm.process_event(Events::Event2).is_err());
-        assert!(sm.process_event(Events::Event4).is_err());
+
+    // The oldest entry was replaced
+    let entries: Vec<_> = sm
+        .history()
+        .iter()
+        .map(|entry| {
+            (
+                entry.from,
+                entry.event,
+                entry.to,
+                entry.outcome,
+                entry.timestamp,
+            )
+        })
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            (
+                StateKind::Idle,
+                EventKind::Stop,
+                StateKind::Idle,
+                Outcome::Failed,
+                2
+            ),
+            (
+                StateKind::Idle,
+                EventKind::Start,
+                StateKind::Running,
+                Outcome::Transition,
+                3
+            ),
+            (
+                StateKind::Running,
+                EventKind::Tick,
+                StateKind::Running,
+                Outcome::Internal,
+                4
+            ),
+        ]
+    );
+    assert_eq!(sm.history().len(), sm.history().capacity());
+    assert_eq!(sm.history().last().unwrap().timestamp, 4);
+
+    sm.clear_history();
+    assert!(sm.history().is_empty());
+}
+
+#[test]
+fn transition_and_state_metrics() {
+    statemachine! {
+        metrics: true,
+        metrics_clock: true,
+        transitions: {
+            *Idle + Connect [online] = Connecting,
+            Connecting + Fail = Retrying,
+            Retrying + Connect = Connecting,
+            Connecting + Tick = _,
+            Connecting + Disconnect = reject(Busy),
+        }
     }
-    {
-        let mut sm = StateMachine::new(Context::default(), State1Data(ActionId::Init));
-        assert_transition_ok!(sm, Events::Event4, ActionId::Action14, States::State4(_));
-        assert_transition_ok!(sm, Events::Event1, ActionId::Action44, States::State4(_));
-        assert_transition_ok!(sm, Events::Event3, ActionId::Action3, States::State4(_));
+
+    #[derive(Default)]
+    struct Context {
+        now: u64,
+    }
+
+    impl StateMachineContext for Context {
+        fn online(&self) -> Result<bool, ()> {
+            Ok(self.now >= 10)
+        }
+
+        fn metrics_clock(&self) -> u64 {
+            self.now
+        }
+    }
+
+    // The time in the starting state is counted from the creation of the state machine
+    let mut sm = StateMachine::new(Context { now: 5 });
+    for (time, event) in [
+        (8, Events::Connect),
+        (15, Events::Connect),
+        (15, Events::Tick),
+        (20, Events::Disconnect),
+        (30, Events::Fail),
+        (70, Events::Connect),
+        (75, Events::Fail),
+        (80, Events::Tick),
+    ] {
+        sm.context_mut().now = time;
+        let _ = sm.process_event(event);
     }
+
+    let metrics = sm.metrics();
+    assert_eq!(metrics.transitions, [1, 2, 1, 1]);
+    assert_eq!(metrics.entries[StateKind::Retrying.index()], 2);
+    assert_eq!(metrics.entries[StateKind::Connecting.index()], 2);
+    assert_eq!(metrics.entries[StateKind::Idle.index()], 0);
+    assert_eq!(metrics.rejected[StateKind::Idle.index()], 1);
+    assert_eq!(metrics.rejected[StateKind::Connecting.index()], 1);
+    assert_eq!(metrics.rejected[StateKind::Retrying.index()], 1);
+    assert_eq!(metrics.time_in_state[StateKind::Idle.index()], 10);
+    assert_eq!(metrics.time_in_state[StateKind::Connecting.index()], 20);
+    assert_eq!(metrics.time_in_state[StateKind::Retrying.index()], 45);
+    assert_eq!(
+        StateMachine::<Context>::STATE_NAMES[StateKind::Retrying.index()],
+        "Retrying"
+    );
+
+    sm.reset_metrics();
+    assert_eq!(sm.metrics().transitions, [0; 4]);
+
+    // After a reset, the time in the current state is counted from the reset
+    sm.context_mut().now = 90;
+    sm.process_event(Events::Connect).unwrap();
+    assert_eq!(sm.metrics().time_in_state[StateKind::Retrying.index()], 10);
 }
+
+#[cfg(feature = "std")]
 #[test]
-fn test_wildcard_states_and_internal_transitions() {
+fn record_and_replay_events() {
+    use smlang::replay::{self, LoggedEvent};
+
     statemachine! {
+        replay: true,
+        events_attr: #[derive(Clone, Debug)],
         transitions: {
-            *State1 + Event2 = State2,
-            State2 + Event3 = State3,
-            _ + Event1 / increment_count,      // Internal transition (implicit: omitting target state)
-            _ + Event3 / increment_count = _ , // Internal transition (explicit: using _ as target state)
-        },
-        states_attr: #[derive(Debug, Clone,  Copy)]
+            *Idle + Measure(u32) [in_range] / store = Measured(u32),
+            Idle + Measure(u32) [!in_range] = Idle,
+            Measured(u32) + Reset = Idle,
+        }
     }
-    #[derive(Debug)]
-    pub struct Context {
-        count: u32,
+
+    struct Sensor {
+        limit: u32,
+    }
+
+    impl StateMachineContext for Sensor {
+        fn in_range(&self, value: &u32) -> Result<bool, ()> {
+            Ok(*value < self.limit)
+        }
+
+        fn store(&mut self, value: u32) -> Result<u32, ()> {
+            Ok(value)
+        }
+    }
+
+    let mut sm = StateMachine::new(Sensor { limit: 10 });
+    let _ = sm.process_event(Events::Reset);
+    sm.start_recording();
+    let _ = sm.process_event(Events::Measure(20));
+    let _ = sm.process_event(Events::Measure(5));
+    let _ = sm.process_event(Events::Reset);
+    let _ = sm.process_event(Events::Reset);
+    let log = sm.stop_recording().unwrap();
+    assert_eq!(
+        log.events,
+        [
+            LoggedEvent {
+                event: Events::Measure(20),
+                // The result of every guard call, not of the guard expression `!in_range`
+                guards: vec![false, false],
+            },
+            LoggedEvent {
+                event: Events::Measure(5),
+                guards: vec![true],
+            },
+            LoggedEvent {
+                event: Events::Reset,
+                guards: vec![],
+            },
+            LoggedEvent {
+                event: Events::Reset,
+                guards: vec![],
+            },
+        ]
+    );
+    assert!(matches!(log.events[0].event, Events::Measure(20)));
+
+    // The recorded guard results are used, even though the guards of this context disagree
+    let mut sm = StateMachine::new(Sensor { limit: 0 });
+    let results = replay::replay(&mut sm, &log);
+    assert_eq!(results, [Ok(()), Ok(()), Ok(()), Err(Error::InvalidEvent)]);
+    assert!(matches!(sm.state(), States::Idle));
+
+    // Without recorded results, the guards of the context are called
+    let mut sm = StateMachine::new(Sensor { limit: 10 });
+    sm.replay_event(&LoggedEvent {
+        event: Events::Measure(1),
+        guards: vec![],
+    })
+    .unwrap();
+    assert!(matches!(sm.state(), States::Measured(1)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn mock_context() {
+    use smlang::mock::Call;
+
+    statemachine! {
+        mock: true,
+        transitions: {
+            *Idle + Start(u32) [valid && !busy] / start = Running(u32),
+            Idle + Start(u32) = Idle,
+            Running(u32) + Stop / stop = Idle,
+        }
+    }
+
+    let mut mock = MockContext::new();
+    mock.set_guard("valid", true)
+        .set_guard("busy", false)
+        .set_action("start", 7u32);
+    let mut sm = StateMachine::new(mock);
+
+    sm.process_event(Events::Start(1)).unwrap();
+    assert!(matches!(sm.state(), States::Running(7)));
+    sm.process_event(Events::Stop).unwrap();
+    assert_eq!(
+        sm.context_mut().take_calls(),
+        [
+            Call::Guard("valid"),
+            Call::Guard("busy"),
+            Call::Exit("Idle"),
+            Call::Action("start"),
+            Call::Entry("Running"),
+            Call::Exit("Running"),
+            Call::Action("stop"),
+            Call::Entry("Idle"),
+        ]
+    );
+
+    sm.context_mut().set_guard("valid", false);
+    sm.process_event(Events::Start(1)).unwrap();
+    assert!(matches!(sm.state(), States::Idle));
+    assert_eq!(sm.context().calls(), [Call::Guard("valid")]);
+}
+
+#[test]
+fn interrupted_transitions() {
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context as TaskContext, Waker};
+
+    statemachine! {
+        async_hooks: true,
+        states_attr: #[derive(Debug)],
+        transitions: {
+            *Idle + Start / async start = Running,
+            Running + Stop = Idle,
+        }
+    }
+
+    #[derive(Default)]
+    struct Context {
+        calls: Vec<&'static str>,
     }
+
     impl StateMachineContext for Context {
-        fn increment_count(&mut self) -> Result<(), ()> {
-            self.count += 1;
+        async fn start(&mut self) -> Result<(), ()> {
+            smol::future::yield_now().await;
+            self.calls.push("start");
+            Ok(())
+        }
+
+        async fn on_exit_idle(&mut self) -> Result<(), ()> {
+            smol::future::yield_now().await;
+            self.calls.push("exit idle");
+            Ok(())
+        }
+
+        async fn transition_callback(&self, _: &States, _: &States) -> Result<(), ()> {
+            smol::future::yield_now().await;
+            Ok(())
+        }
+
+        async fn on_entry_running(&mut self) -> Result<(), ()> {
+            smol::future::yield_now().await;
+            self.calls.push("enter running");
             Ok(())
         }
+
+        async fn on_exit_running(&mut self) -> Result<(), ()> {
+            Err(())
+        }
     }
 
-    let mut sm = StateMachine::new(Context { count: 0 });
+    // Every await point yields once, so polling `polls` times and dropping the future interrupts
+    // the transition in the exit function, the action, the transition callback or the entry
+    // function
+    let expected_calls: [&[&str]; 4] = [
+        &[],
+        &["exit idle"],
+        &["exit idle", "start"],
+        &["exit idle", "start"],
+    ];
+    for (polls, calls) in (1..).zip(expected_calls) {
+        let mut sm = StateMachine::new(Context::default());
+        {
+            let mut future = pin!(sm.process_event(Events::Start));
+            let mut cx = TaskContext::from_waker(Waker::noop());
+            for _ in 0..polls {
+                assert!(future.as_mut().poll(&mut cx).is_pending());
+            }
+        }
 
-    assert_transition!(sm, Events::Event1, States::State1, 1);
-    assert_transition!(sm, Events::Event2, States::State2, 1);
-    assert_transition!(sm, Events::Event3, States::State3, 1);
-    assert_transition!(sm, Events::Event1, States::State3, 2);
-    assert_transition!(sm, Events::Event3, States::State3, 3);
+        assert!(sm.is_transition_interrupted());
+        assert_eq!(sm.interrupted_transition().unwrap().event, "Start");
+        assert_eq!(sm.context().calls, calls);
+        // The state is changed before the entry function is called
+        if polls == 4 {
+            assert!(matches!(sm.state(), &States::Running));
+        } else {
+            assert!(matches!(sm.state(), &States::Idle));
+        }
 
-    assert!(sm.process_event(Events::Event2).is_err()); // InvalidEvent
-    assert_eq!(States::State3, sm.state);
+        let result = smol::block_on(sm.process_event(Events::Start));
+        assert_eq!(result, Err(Error::TransitionInterrupted));
+
+        sm.recover();
+        assert!(!sm.is_transition_interrupted());
+    }
+
+    // A future which is dropped before it is polled does not start the transition
+    let mut sm = StateMachine::new(Context::default());
+    drop(sm.process_event(Events::Start));
+    assert!(!sm.is_transition_interrupted());
+
+    // A completed or failed transition is not interrupted
+    smol::block_on(sm.process_event(Events::Start)).unwrap();
+    assert!(!sm.is_transition_interrupted());
+    assert!(matches!(sm.state(), &States::Running));
+
+    let result = smol::block_on(sm.process_event(Events::Stop));
+    assert_eq!(result, Err(Error::ExitFailed(())));
+    assert!(!sm.is_transition_interrupted());
 }
+
 #[test]
-fn test_specify_attrs() {
-    #![deny(non_camel_case_types)]
-    use serde::Serialize;
+fn guard_expressions() {
+    #[derive(PartialEq, Display)]
+    pub struct Entry(pub u32);
+
     statemachine! {
+        states_attr: #[derive(Display, Debug)],
         transitions: {
-            *State1 + tostate2 = State2,
-            State2 + tostate3 / increment_count = State3
-        },
-        states_attr: #[derive(Debug, Clone, Copy, Serialize)] #[non_exhaustive] #[repr(u8)] #[serde(tag="type")],
-        events_attr: #[derive(Debug)] #[allow(non_camel_case_types)]
+            *Init + Login(&'a Entry) [valid_entry] / attempt = LoggedIn,
+            Init + Login(&'a Entry) [!valid_entry && !too_many_attempts] / attempt = Init,
+            Init + Login(&'a Entry) [!valid_entry && too_many_attempts] / attempt = LoginDenied,
+            LoggedIn + Logout / reset = Init,
+        }
     }
 
-    #[derive(Debug, Default)]
+    /// Context
     pub struct Context {
-        count: u32,
+        password: u32,
+        attempts: u32,
+    }
+    impl StateMachineContext for Context {
+        fn valid_entry(&self, e: &Entry) -> Result<bool, ()> {
+            Ok(e.0 == self.password)
+        }
+        fn too_many_attempts(&self, _e: &Entry) -> Result<bool, ()> {
+            Ok(self.attempts >= 3)
+        }
+        fn reset(&mut self) -> Result<(), ()> {
+            self.attempts = 0;
+            Ok(())
+        }
+        fn attempt(&mut self, _e: &Entry) -> Result<(), ()> {
+            self.attempts += 1;
+            Ok(())
+        }
     }
 
+    let mut sm = StateMachine::new(Context {
+        password: 42,
+        attempts: 0,
+    });
+    assert!(matches!(sm.state(), &States::Init));
+
+    let bad_entry = Entry(10);
+    let good_entry = Entry(42);
+
+    let _ = sm.process_event(Events::Login(&bad_entry));
+    assert_eq!(sm.context().attempts, 1);
+    assert!(matches!(sm.state(), &States::Init));
+
+    let _ = sm.process_event(Events::Login(&bad_entry));
+    assert_eq!(sm.context().attempts, 2);
+    assert!(matches!(sm.state(), &States::Init));
+
+    let _ = sm.process_event(Events::Login(&good_entry));
+    assert_eq!(sm.context().attempts, 3);
+    assert!(matches!(sm.state(), &States::LoggedIn));
+
+    let _ = sm.process_event(Events::Logout);
+    assert_eq!(sm.context().attempts, 0);
+    assert!(matches!(sm.state(), &States::Init));
+
+    let _ = sm.process_event(Events::Login(&bad_entry));
+    let _ = sm.process_event(Events::Login(&bad_entry));
+    let _ = sm.process_event(Events::Login(&bad_entry));
+    assert_eq!(sm.context().attempts, 3);
+    assert!(matches!(sm.state(), &States::Init));
+
+    // exhausted attempts
+    let _ = sm.process_event(Events::Login(&bad_entry));
+    assert_eq!(sm.context().attempts, 4);
+    assert!(matches!(sm.state(), &States::LoginDenied));
+
+    // Invalid event, as we are in a final state
+    assert_eq!(
+        sm.process_event(Events::Login(&good_entry)),
+        Err(Error::InvalidEvent)
+    );
+    assert_eq!(sm.context().attempts, 4);
+    assert!(matches!(sm.state(), &States::LoginDenied));
+}
+#[test]
+fn guarded_transition_before_unguarded() {
+    use smlang::statemachine;
+    statemachine! {
+        transitions: {
+            *State1 + Event1 [guard] / disable = State2,
+            State1 + Event1 = Fault,
+            State2 + Event1 = State1,
+        }
+    }
+    struct Context {
+        pub enabled: bool,
+    }
     impl StateMachineContext for Context {
-        fn increment_count(&mut self) -> Result<(), ()> {
-            self.count += 1;
+        fn guard(&self) -> Result<bool, ()> {
+            Ok(self.enabled)
+        }
+
+        fn disable(&mut self) -> Result<(), ()> {
+            self.enabled = false;
             Ok(())
         }
     }
-    let mut sm = StateMachine::new(Context::default());
+    let mut sm = StateMachine::new(Context { enabled: true });
+    sm.process_event(Events::Event1).unwrap();
+    assert!(matches!(sm.state(), &States::State2));
 
-    assert_eq!(sm.state().clone(), States::State1);
-    assert_transition!(sm, Events::tostate2, States::State2, 0);
-    assert_transition!(sm, Events::tostate3, States::State3, 1);
+    sm.process_event(Events::Event1).unwrap();
+    assert!(matches!(sm.state(), &States::State1));
+
+    sm.process_event(Events::Event1).unwrap();
+    assert!(matches!(sm.state(), &States::Fault));
+}
+
+#[test]
+fn guard_errors() {
+    use smlang::statemachine;
+    statemachine! {
+        transitions: {
+            *Init + Event1 [guard] = Done,
+        }
+    }
+
+    struct Context {
+        pub guard_passable: bool,
+        pub guard_errors: bool,
+    }
+    impl StateMachineContext for Context {
+        fn guard(&self) -> Result<bool, ()> {
+            if self.guard_errors {
+                Err(())
+            } else {
+                Ok(self.guard_passable)
+            }
+        }
+    }
+
+    let mut sm = StateMachine::new(Context {
+        guard_passable: false,
+        guard_errors: true,
+    });
+
+    // Test attempting to transition when the guard fails.
+    sm.context_mut().guard_errors = true;
+    assert!(sm.process_event(Events::Event1).is_err());
+    assert!(matches!(sm.state(), &States::Init));
+
+    // Test attempting to transition when the guard is not passable.
+    sm.context_mut().guard_errors = false;
+    assert!(sm.process_event(Events::Event1).is_err());
+    assert!(matches!(sm.state(), &States::Init));
+
+    assert!(sm.process_event(Events::Event1).is_err());
+    assert!(matches!(sm.state(), &States::Init));
+
+    sm.context_mut().guard_passable = true;
+    sm.process_event(Events::Event1).unwrap();
+    assert!(matches!(sm.state(), &States::Done));
+}
+#[test]
+fn test_internal_transition_with_data() {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct State1Data(pub ActionId);
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct State3Data(pub ActionId);
+
+    statemachine! {
+        transitions: {
+            *State1(State1Data) + Event2 / action12 = State2,
+            State1(State1Data) + Event3 / action13 = State3(State3Data),
+            State1(State1Data) + Event4 / action14 = State4(State3Data),
+
+            State2 + Event3 / action23 = State3(State3Data),
+            State4(State3Data) + Event1 / action44 = _, // Same as State4(State3Data) + Event1 / action44
+
+            // TRANSITION : _ + Event3 / increment_count = _, IS EQUIVALENT TO THE FOLLOWING TWO:
+            // State3(State3Data) + Event3 / action_3 = State3(State3Data),
+            // State4(State3Data) + Event3 / action_3 = State4(State3Data),
+            _ + Event3 / action_3 = _,
+        },
+        states_attr: #[derive(Debug, Clone,  Copy, Eq)]
+    }
+    /// Context
+    #[derive(Default, Debug, PartialEq, Eq)]
+    pub struct Context {
+        action: ActionId,
+    }
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+    enum ActionId {
+        #[default]
+        Init,
+        Action3,
+        Action12,
+        Action13,
+        Action14,
+        Action23,
+        Action44,
+    }
+    impl StateMachineContext for Context {
+        fn action_3(&mut self, d: &State3Data) -> Result<State3Data, ()> {
+            self.action = ActionId::Action3;
+            Ok(*d)
+        }
+
+        fn action44(&mut self, _d: &State3Data) -> Result<State3Data, ()> {
+            self.action = ActionId::Action44;
+            Ok(State3Data(ActionId::Action44))
+        }
+        fn action14(&mut self, _d: &State1Data) -> Result<State3Data, ()> {
+            self.action = ActionId::Action14;
+            Ok(State3Data(ActionId::Action14))
+        }
+        fn action12(&mut self, _d: &State1Data) -> Result<(), ()> {
+            self.action = ActionId::Action12;
+            Ok(())
+        }
+        fn action13(&mut self, _d: &State1Data) -> Result<State3Data, ()> {
+            self.action = ActionId::Action13;
+            Ok(State3Data(ActionId::Action13))
+        }
+        fn action23(&mut self) -> Result<State3Data, ()> {
+            self.action = ActionId::Action23;
+            Ok(State3Data(ActionId::Action23))
+        }
+    }
+
+    {
+        let mut sm = StateMachine::new(Context::default(), State1Data(ActionId::Init));
+        matches!(States::State2, States::State2);
+        assert_transition_ok!(sm, Events::Event2, ActionId::Action12, States::State2);
+        assert!(sm.process_event(Events::Event1).is_err());
+        assert!(sm.process_event(Events::Event2).is_err());
+        assert!(sm.process_event(Events::Event4).is_err());
+        assert_transition_ok!(sm, Events::Event3, ActionId::Action23, States::State3(_));
+        assert_transition_ok!(sm, Events::Event3, ActionId::Action3, States::State3(_));
+        assert_transition_ok!(sm, Events::Event3, ActionId::Action3, States::State3(_));
+        assert!(sm.process_event(Events::Event1).is_err());
+        assert!(sm.process_event(Events::Event2).is_err());
+        assert!(sm.process_event(Events::Event4).is_err());
+    }
+    {
+        let mut sm = StateMachine::new(Context::default(), State1Data(ActionId::Init));
+        assert_transition_ok!(sm, Events::Event3, ActionId::Action13, States::State3(_));
+        assert!(sm.process_event(Events::Event1).is_err());
+        assert!(sm.process_event(Events::Event2).is_err());
+        assert!(sm.process_event(Events::Event4).is_err());
+    }
+    {
+        let mut sm = StateMachine::new(Context::default(), State1Data(ActionId::Init));
+        assert_transition_ok!(sm, Events::Event4, ActionId::Action14, States::State4(_));
+        assert_transition_ok!(sm, Events::Event1, ActionId::Action44, States::State4(_));
+        assert_transition_ok!(sm, Events::Event3, ActionId::Action3, States::State4(_));
+    }
+}
+#[test]
+fn test_wildcard_states_and_internal_transitions() {
+    statemachine! {
+        transitions: {
+            *State1 + Event2 = State2,
+            State2 + Event3 = State3,
+            _ + Event1 / increment_count,      // Internal transition (implicit: omitting target state)
+            _ + Event3 / increment_count = _ , // Internal transition (explicit: using _ as target state)
+        },
+        states_attr: #[derive(Debug, Clone,  Copy)]
+    }
+    #[derive(Debug)]
+    pub struct Context {
+        count: u32,
+    }
+    impl StateMachineContext for Context {
+        fn increment_count(&mut self) -> Result<(), ()> {
+            self.count += 1;
+            Ok(())
+        }
+    }
+
+    let mut sm = StateMachine::new(Context { count: 0 });
+
+    assert_transition!(sm, Events::Event1, States::State1, 1);
+    assert_transition!(sm, Events::Event2, States::State2, 1);
+    assert_transition!(sm, Events::Event3, States::State3, 1);
+    assert_transition!(sm, Events::Event1, States::State3, 2);
+    assert_transition!(sm, Events::Event3, States::State3, 3);
+
+    assert!(sm.process_event(Events::Event2).is_err()); // InvalidEvent
+    assert_eq!(States::State3, sm.state);
+}
+#[test]
+fn test_specify_attrs() {
+    #![deny(non_camel_case_types)]
+    use serde::Serialize;
+    statemachine! {
+        transitions: {
+            *State1 + tostate2 = State2,
+            State2 + tostate3 / increment_count = State3
+        },
+        states_attr: #[derive(Debug, Clone, Copy, Serialize)] #[non_exhaustive] #[repr(u8)] #[serde(tag="type")],
+        events_attr: #[derive(Debug)] #[allow(non_camel_case_types)]
+    }
+
+    #[derive(Debug, Default)]
+    pub struct Context {
+        count: u32,
+    }
+
+    impl StateMachineContext for Context {
+        fn increment_count(&mut self) -> Result<(), ()> {
+            self.count += 1;
+            Ok(())
+        }
+    }
+    let mut sm = StateMachine::new(Context::default());
+
+    assert_eq!(sm.state().clone(), States::State1);
+    assert_transition!(sm, Events::tostate2, States::State2, 0);
+    assert_transition!(sm, Events::tostate3, States::State3, 1);
+}
+
+#[test]
+fn write_dot_highlights_current_state_and_last_transition() {
+    statemachine! {
+        name: Door,
+        transitions: {
+            *Closed + Open [is_unlocked] / open = Opened,
+            Opened + Close = Closed,
+            Closed + Knock,
+        }
+    }
+
+    struct Context;
+    impl DoorStateMachineContext for Context {
+        fn is_unlocked(&self) -> Result<bool, ()> {
+            Ok(true)
+        }
+        fn open(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    let mut sm = DoorStateMachine::new(Context);
+    assert_eq!(
+        DoorStateMachine::<Context>::STATE_NAMES,
+        ["Closed", "Opened"]
+    );
+    assert_eq!(
+        DoorStateMachine::<Context>::TRANSITIONS[0].to_string(),
+        "Closed + Open [is_unlocked] / open = Opened"
+    );
+    assert!(sm.last_transition().is_none());
+
+    let mut dot = String::new();
+    sm.write_dot(&mut dot).unwrap();
+    assert!(dot.starts_with("digraph Door {"));
+    assert!(dot.contains("s -> Closed"));
+    assert!(dot.contains("Closed [shape=box color=\"black\" fillcolor=\"#66cc66\""));
+    assert!(!dot.contains("penwidth=3];"));
+
+    sm.process_event(DoorEvents::Open).unwrap();
+    assert_eq!(sm.last_transition().unwrap().to, "Opened");
+
+    let mut dot = String::new();
+    sm.write_dot(&mut dot).unwrap();
+    assert!(dot.contains("Opened [shape=box color=\"black\" fillcolor=\"#66cc66\""));
+    assert!(dot.contains("Closed [shape=box color=\"red\""));
+    assert!(dot.contains(
+        "Closed -> Opened [label=\"Open\\n[is_unlocked] / open\" color=\"black\" penwidth=3];"
+    ));
+
+    sm.process_event(DoorEvents::Close).unwrap();
+    sm.process_event(DoorEvents::Knock).unwrap();
+    assert_eq!(
+        sm.last_transition(),
+        Some(&DoorStateMachine::<Context>::TRANSITIONS[2])
+    );
+}
+
+#[test]
+fn terminal_states() {
+    statemachine! {
+        terminal_states: [Off],
+        transitions: {
+            *Idle + Start = Running,
+            Running + Fault = Recovering,
+            Recovering + Recovered = Idle,
+            _ + Shutdown = Off,
+        }
+    }
+
+    struct Context;
+    impl StateMachineContext for Context {}
+
+    let mut sm = StateMachine::new(Context);
+    sm.process_event(Events::Start).unwrap();
+    sm.process_event(Events::Fault).unwrap();
+    sm.process_event(Events::Recovered).unwrap();
+    assert!(matches!(sm.state(), &States::Idle));
+    sm.process_event(Events::Shutdown).unwrap();
+    assert!(matches!(sm.state(), &States::Off));
+}
+
+#[test]
+fn exhaustive_with_ignored_events() {
+    statemachine! {
+        exhaustive: true,
+        transitions: {
+            *Idle + Start / start = Running(u32),
+            Idle + Stop = ignore,
+            Running(u32) + Stop = Idle,
+            Running(u32) + Start = ignore,
+            _ + Ping = ignore,
+        }
+    }
+
+    struct Context;
+    impl StateMachineContext for Context {
+        fn start(&mut self) -> Result<u32, ()> {
+            Ok(42)
+        }
+    }
+
+    let mut sm = StateMachine::new(Context);
+    sm.process_event(Events::Stop).unwrap();
+    sm.process_event(Events::Ping).unwrap();
+    assert!(matches!(sm.state(), &States::Idle));
+    assert!(sm.last_transition().is_none());
+
+    sm.process_event(Events::Start).unwrap();
+    sm.process_event(Events::Start).unwrap();
+    sm.process_event(Events::Ping).unwrap();
+    assert!(matches!(sm.state(), &States::Running(42)));
+
+    // Ignored events are not part of the transition table
+    assert_eq!(StateMachine::<Context>::TRANSITIONS.len(), 2);
+}
+
+#[test]
+// Lint warnings are reported as the use of a deprecated item
+#[allow(deprecated)]
+fn lint_levels() {
+    statemachine! {
+        lints: { unreachable_state: warn },
+        transitions: {
+            *Idle + Start = Running,
+            Running + Stop = Idle,
+            Recovering + Recovered = Idle,
+        }
+    }
+
+    struct Context;
+    impl StateMachineContext for Context {}
+
+    let mut sm = StateMachine::new_with_state(Context, States::Recovering);
+    sm.process_event(Events::Recovered).unwrap();
+    sm.process_event(Events::Start).unwrap();
+    sm.process_event(Events::Stop).unwrap();
+    assert!(matches!(sm.state(), &States::Idle));
+}
+
+#[test]
+// Allowed unreachable transitions do not make rustc warn about unreachable code
+#[deny(unreachable_code)]
+fn allowed_unreachable_transition() {
+    statemachine! {
+        lints: { unreachable_transition: allow },
+        transitions: {
+            *Idle + Start = Running,
+            Idle + Start = Stopped,
+        }
+    }
+
+    struct Context;
+    impl StateMachineContext for Context {}
+
+    let mut sm = StateMachine::new(Context);
+    sm.process_event(Events::Start).unwrap();
+    assert!(matches!(sm.state(), &States::Running));
+}
+
+#[test]
+fn rejected_events() {
+    statemachine! {
+        name: Door,
+        exhaustive: true,
+        states_attr: #[derive(Debug)],
+        transitions: {
+            *Closed + Open [is_locked] = reject(DoorLocked),
+            Closed + Open = Opened,
+            Closed + Close = ignore,
+            Opened + Close = Closed,
+            Opened + Open = ignore,
+            _ + Lock = reject(NotSupported),
+        }
+    }
+
+    struct Context {
+        locked: bool,
+    }
+    impl DoorStateMachineContext for Context {
+        fn is_locked(&self) -> Result<bool, ()> {
+            Ok(self.locked)
+        }
+    }
+
+    let mut sm = DoorStateMachine::new(Context { locked: true });
+    assert_eq!(
+        sm.process_event(DoorEvents::Open),
+        Err(DoorError::Rejected(DoorRejectReason::DoorLocked))
+    );
+    assert!(matches!(sm.state(), &DoorStates::Closed));
+
+    sm.context_mut().locked = false;
+    sm.process_event(DoorEvents::Open).unwrap();
+    assert!(matches!(sm.state(), &DoorStates::Opened));
+    assert_eq!(
+        sm.process_event(DoorEvents::Lock),
+        Err(DoorError::Rejected(DoorRejectReason::NotSupported))
+    );
+
+    sm.process_event(DoorEvents::Close).unwrap();
+    assert!(matches!(sm.state(), &DoorStates::Closed));
+
+    // Rejected events are not part of the transition table
+    assert_eq!(DoorStateMachine::<Context>::TRANSITIONS.len(), 2);
+}
+
+#[test]
+fn mailbox_runner() {
+    use smlang::runner::{Mailbox, Runner, Sender};
+
+    statemachine! {
+        transitions: {
+            *Idle + Start / start = Running,
+            Running + Tick [is_done] = Done,
+            Running + Tick / tick,
+        }
+    }
+
+    struct Context<'a> {
+        ticks: u32,
+        sender: Sender<'a, Events, 2>,
+    }
+
+    impl StateMachineContext for Context<'_> {
+        fn is_done(&self) -> Result<bool, ()> {
+            Ok(self.ticks == 3)
+        }
+
+        fn start(&mut self) -> Result<(), ()> {
+            self.sender.send(Events::Tick).map_err(|_| ())
+        }
+
+        // Events can be queued while an event is processed
+        fn tick(&mut self) -> Result<(), ()> {
+            self.ticks += 1;
+            self.sender.send(Events::Tick).map_err(|_| ())
+        }
+    }
+
+    let mailbox = Mailbox::new();
+    let sender = mailbox.sender();
+    let mut runner = Runner::new(
+        StateMachine::new(Context {
+            ticks: 0,
+            sender: sender.clone(),
+        }),
+        &mailbox,
+    );
+
+    sender.send(Events::Start).unwrap();
+    sender.send(Events::Start).unwrap();
+    assert!(sender.send(Events::Start).is_err());
+
+    let mut errors = Vec::new();
+    assert_eq!(runner.run(|error| errors.push(error)), 6);
+    assert_eq!(errors, [Error::InvalidEvent]);
+    assert!(matches!(runner.machine().state(), &States::Done));
+    assert!(mailbox.is_empty());
+}
+
+#[test]
+fn mailbox_runner_async() {
+    use smlang::runner::{Mailbox, Runner};
+
+    statemachine! {
+        transitions: {
+            *State1 + Event1 [async guard1] = State2,
+            State2 + Event2 = State1,
+        }
+    }
+
+    struct Context;
+    impl StateMachineContext for Context {
+        async fn guard1(&self) -> Result<bool, ()> {
+            Ok(true)
+        }
+    }
+
+    let mailbox: Mailbox<Events, 4> = Mailbox::new();
+    let mut runner = Runner::new(StateMachine::new(Context), &mailbox);
+    for event in [Events::Event1, Events::Event2, Events::Event1] {
+        runner.sender().send(event).unwrap();
+    }
+
+    let processed = smol::block_on(runner.drain_async(|_| panic!("no errors expected")));
+    assert_eq!(processed, 3);
+    assert!(matches!(runner.into_inner().state(), &States::State2));
+}
+
+#[test]
+fn mailbox_runner_waits_for_events() {
+    use smlang::runner::{Mailbox, Runner};
+
+    statemachine! {
+        transitions: {
+            *State1 + Event1 [async guard1] = State2,
+            State2 + Event2 = State1,
+        }
+    }
+
+    struct Context;
+    impl StateMachineContext for Context {
+        async fn guard1(&self) -> Result<bool, ()> {
+            Ok(true)
+        }
+    }
+
+    // The mailbox holds a single event, so every event is processed before the next is sent
+    let mailbox: Mailbox<Events, 1> = Mailbox::new();
+    let mut runner = Runner::new(StateMachine::new(Context), &mailbox);
+    let sender = runner.sender();
+
+    smol::block_on(smol::future::or(
+        async { match runner.run_async(|_| panic!("no errors expected")).await {} },
+        async {
+            for event in [Events::Event1, Events::Event2, Events::Event1] {
+                sender.send(event).unwrap();
+                smol::future::yield_now().await;
+            }
+        },
+    ));
+    assert!(mailbox.is_empty());
+    assert!(matches!(runner.machine().state(), &States::State2));
+}
+
+#[cfg(feature = "critical-section")]
+#[test]
+fn static_mailbox_fed_from_another_thread() {
+    use smlang::runner::{Mailbox, Runner};
+
+    statemachine! {
+        transitions: {
+            *Off + Toggle = On,
+            On + Toggle = Off,
+        }
+    }
+
+    struct Context;
+    impl StateMachineContext for Context {}
+
+    static MAILBOX: Mailbox<Events, 8> = Mailbox::new();
+    let mut runner = Runner::new(StateMachine::new(Context), &MAILBOX);
+
+    let producer = std::thread::spawn(|| {
+        for _ in 0..3 {
+            MAILBOX.send(Events::Toggle).unwrap();
+        }
+    });
+    assert!(runner.run(|_| panic!("no errors expected")) <= 3);
+    producer.join().unwrap();
+    runner.run(|_| panic!("no errors expected"));
+    assert!(matches!(runner.machine().state(), &States::On));
+}
+
+#[cfg(feature = "critical-section")]
+#[test]
+fn send_runner_can_be_spawned() {
+    use smlang::runner::{Mailbox, Runner};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    statemachine! {
+        async_send: true,
+        transitions: {
+            *Off + Toggle / async switch_on = On,
+            On + Toggle = Off,
+        }
+    }
+
+    struct Context {
+        switched_on: mpsc::Sender<()>,
+    }
+
+    impl StateMachineContext for Context {
+        async fn switch_on(&mut self) -> Result<(), ()> {
+            smol::future::yield_now().await;
+            self.switched_on.send(()).unwrap();
+            Ok(())
+        }
+    }
+
+    static MAILBOX: Mailbox<Events, 8> = Mailbox::new();
+    let (switched_on, on) = mpsc::channel();
+    let mut runner = Runner::new(StateMachine::new(Context { switched_on }), &MAILBOX);
+
+    // The runner waits for events on a thread of the multi-threaded executor
+    let task = smol::spawn(async move {
+        runner
+            .run_async_send(|_| panic!("no errors expected"))
+            .await
+    });
+
+    for _ in 0..3 {
+        MAILBOX.send(Events::Toggle).unwrap();
+    }
+    for _ in 0..2 {
+        on.recv_timeout(Duration::from_secs(10)).unwrap();
+    }
+    drop(task);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn channel_runner() {
+    use smlang::runner::ChannelRunner;
+
+    statemachine! {
+        transitions: {
+            *Off + Toggle = On,
+            On + Toggle = Off,
+        }
+    }
+
+    struct Context;
+    impl StateMachineContext for Context {}
+
+    let (mut runner, sender) = ChannelRunner::new(StateMachine::new(Context), 2);
+    let threads: Vec<_> = (0..3)
+        .map(|_| {
+            let sender = sender.clone();
+            std::thread::spawn(move || sender.send(Events::Toggle).unwrap())
+        })
+        .collect();
+    drop(sender);
+
+    assert_eq!(runner.run(|_| panic!("no errors expected")), 3);
+    threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+    assert!(matches!(runner.machine().state(), &States::On));
+}
+
+#[cfg(feature = "critical-section")]
+#[test]
+fn shared_state_machine() {
+    use smlang::SharedStateMachine;
+
+    statemachine! {
+        transitions: {
+            *Idle + Press / count = Idle,
+            Idle + Hold = Locked,
+        }
+    }
+
+    struct Context {
+        presses: u32,
+    }
+
+    impl StateMachineContext for Context {
+        fn count(&mut self) -> Result<(), ()> {
+            self.presses += 1;
+            Ok(())
+        }
+    }
+
+    static SM: SharedStateMachine<StateMachine<Context>> =
+        SharedStateMachine::new(StateMachine::new(Context { presses: 0 }));
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            std::thread::spawn(|| (0..100).for_each(|_| SM.process_event(Events::Press).unwrap()))
+        })
+        .collect();
+    threads
+        .into_iter()
+        .for_each(|thread| thread.join().unwrap());
+
+    assert_eq!(SM.with(|sm| sm.context().presses), 400);
+    SM.process_event(Events::Hold).unwrap();
+    assert_eq!(SM.process_event(Events::Press), Err(Error::InvalidEvent));
+    assert!(SM.with(|sm| matches!(sm.state(), &States::Locked)));
+}
+
+#[test]
+fn state_and_event_kinds() {
+    statemachine! {
+        transitions: {
+            *Idle + Start(u32) / start = Running(u32),
+            Running(u32) + Stop = Idle,
+        }
+    }
+
+    struct Context;
+    impl StateMachineContext for Context {
+        fn start(&mut self, speed: u32) -> Result<u32, ()> {
+            Ok(speed)
+        }
+    }
+
+    let mut sm = StateMachine::new(Context);
+    assert_eq!(sm.state().kind(), StateKind::Idle);
+    sm.process_event(Events::Start(3)).unwrap();
+    assert_eq!(sm.state().kind(), StateKind::Running);
+    sm.process_event(Events::Stop).unwrap();
+    assert_eq!(sm.state().kind(), StateKind::Idle);
+    assert_eq!(Events::Start(3).kind(), EventKind::Start);
+    assert_eq!(EventKind::Stop.name(), "Stop");
+    assert_eq!(StateKind::Running.to_string(), "Running");
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn default_log_functions() {
+    use smlang::log::{self, Log, Metadata, Record};
+    use std::sync::Mutex;
+
+    struct Logger(Mutex<Vec<String>>);
+
+    impl Log for Logger {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            self.0.lock().unwrap().push(record.args().to_string());
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: Logger = Logger(Mutex::new(Vec::new()));
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Debug);
+
+    statemachine! {
+        name: Logged,
+        transitions: {
+            *Idle + Start(u32) [valid] / start = Running(u32),
+        }
+    }
+
+    struct Context;
+    impl LoggedStateMachineContext for Context {
+        fn valid(&self, speed: &u32) -> Result<bool, ()> {
+            Ok(*speed > 0)
+        }
+
+        fn start(&mut self, speed: u32) -> Result<u32, ()> {
+            Ok(speed)
+        }
+    }
+
+    let mut sm = LoggedStateMachine::new(Context);
+    sm.process_event(LoggedEvents::Start(3)).unwrap();
+
+    let messages: Vec<_> = LOGGER
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|message| message.starts_with("LoggedStateMachine"))
+        .cloned()
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "LoggedStateMachine: processing Start in state Idle",
+            "LoggedStateMachine: guard valid passed",
+            "LoggedStateMachine: action start",
+        ]
+    );
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn tracing_spans() {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    mod blocking {
+        use smlang::statemachine;
+
+        statemachine! {
+            name: Blocking,
+            transitions: {
+                *Idle + Start [ready] / start = Running,
+            }
+        }
+    }
+
+    mod nonblocking {
+        use smlang::statemachine;
+
+        statemachine! {
+            name: Nonblocking,
+            transitions: {
+                *Idle + Start [async ready] = Running,
+            }
+        }
+    }
+
+    struct Context;
+
+    impl blocking::BlockingStateMachineContext for Context {
+        fn ready(&self) -> Result<bool, ()> {
+            Ok(true)
+        }
+
+        fn start(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    impl nonblocking::NonblockingStateMachineContext for Context {
+        async fn ready(&self) -> Result<bool, ()> {
+            smol::future::yield_now().await;
+            Ok(false)
+        }
+    }
+
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .without_time()
+        .with_target(false)
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let mut sm = blocking::BlockingStateMachine::new(Context);
+        sm.process_event(blocking::BlockingEvents::Start).unwrap();
+
+        let mut sm = nonblocking::NonblockingStateMachine::new(Context);
+        let result = smol::block_on(sm.process_event(nonblocking::NonblockingEvents::Start));
+        assert!(matches!(
+            result,
+            Err(nonblocking::NonblockingError::TransitionsFailed)
+        ));
+    });
+
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    let lines: Vec<_> = output.lines().map(str::trim).collect();
+    assert_eq!(
+        lines,
+        [
+            "DEBUG BlockingStateMachine{state=Idle event=Start}: guard guard=\"ready\" passed=true",
+            "DEBUG BlockingStateMachine{state=Idle event=Start}: action action=\"start\"",
+            "DEBUG BlockingStateMachine{state=Idle event=Start}: transition completed state=Running",
+            "DEBUG NonblockingStateMachine{state=Idle event=Start}: guard guard=\"async ready\" passed=false",
+            "DEBUG NonblockingStateMachine{state=Idle event=Start}: transition failed error=TransitionsFailed",
+        ]
+    );
+}
+
+#[test]
+fn walk_with_invariants() {
+    use smlang::walk::{walk, Violation};
+
+    statemachine! {
+        transitions: {
+            *Idle + Start = Running,
+            Running + Stop = Idle,
+        }
+    }
+
+    struct Context;
+    impl StateMachineContext for Context {}
+
+    // Errors can be part of the invariant
+    let mut sm = StateMachine::new(Context);
+    let events = [Events::Start, Events::Stop, Events::Stop];
+    assert_eq!(
+        walk(&mut sm, events, |_, result| match result {
+            Err(Error::InvalidEvent) => Err("invalid event"),
+            _ => Ok(()),
+        }),
+        Err(Violation {
+            step: 2,
+            error: "invalid event"
+        })
+    );
+
+    let mut sm = StateMachine::new(Context);
+    let events = [Events::Start, Events::Stop, Events::Start];
+    assert_eq!(
+        walk(&mut sm, events, |_, result| result
+            .as_ref()
+            .map(|_| ())
+            .map_err(|_| ())),
+        Ok(())
+    );
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn arbitrary_random_walk() {
+    use smlang::arbitrary::{Arbitrary, Unstructured};
+    use smlang::walk::random_walk;
+
+    statemachine! {
+        transitions: {
+            *Idle + Start(u8) / start = Running(u8),
+            Running(u8) + Stop = Idle,
+            Running(u8) + Start(u8) / restart = Running(u8),
+        }
+    }
+
+    #[derive(Default)]
+    struct Context {
+        starts: usize,
+    }
+
+    impl StateMachineContext for Context {
+        fn start(&mut self, speed: u8) -> Result<u8, ()> {
+            self.starts += 1;
+            Ok(speed)
+        }
+
+        fn restart(&mut self, _: &u8, speed: u8) -> Result<u8, ()> {
+            Ok(speed)
+        }
+    }
+
+    let data: Vec<u8> = (0..=255).collect();
+    let mut u = Unstructured::new(&data);
+    let mut steps = 0;
+    let mut sm = StateMachine::new(Context::default());
+    random_walk(&mut sm, &mut u, |sm, _| {
+        steps += 1;
+        // Only events from `Idle` call `start`
+        if sm.context().starts > steps {
+            return Err(());
+        }
+        Ok(())
+    })
+    .unwrap();
+    assert!(steps > 1);
+
+    let mut u = Unstructured::new(&data);
+    let kind = StateKind::arbitrary(&mut u).unwrap();
+    assert!(matches!(kind, StateKind::Idle | StateKind::Running));
+}
+
+#[cfg(feature = "proptest")]
+smlang::proptest::proptest! {
+    #[test]
+    fn proptest_random_walk(events in smlang::walk::event_sequences::<proptest_walk::Events>(0..32)) {
+        use proptest_walk::*;
+
+        let mut sm = StateMachine::new(Context);
+        smlang::walk::walk(&mut sm, events, |sm, result| match (sm.state(), result) {
+            (States::Running(speed), Ok(())) if *speed == 0 => Err("running at speed 0"),
+            _ => Ok(()),
+        })
+        .unwrap();
+    }
+}
+
+#[cfg(feature = "proptest")]
+mod proptest_walk {
+    use smlang::statemachine;
+
+    statemachine! {
+        events_attr: #[derive(Debug)],
+        transitions: {
+            *Idle + Start(u8) [valid] / start = Running(u8),
+            Running(u8) + Stop = Idle,
+        }
+    }
+
+    pub struct Context;
+
+    impl StateMachineContext for Context {
+        fn valid(&self, speed: &u8) -> Result<bool, ()> {
+            Ok(*speed > 0)
+        }
+
+        fn start(&mut self, speed: u8) -> Result<u8, ()> {
+            Ok(speed)
+        }
+    }
+}
+
+#[cfg(feature = "coverage")]
+#[test]
+fn transition_coverage() {
+    statemachine! {
+        transitions: {
+            *Idle + Start [ready] = Running,
+            Idle + Start = Failed,
+            Running + Stop [!busy] / halt = Idle,
+            Running + Tick = _,
+            Failed + Start = ignore,
+        }
+    }
+
+    struct Context;
+
+    impl StateMachineContext for Context {
+        fn ready(&self) -> Result<bool, ()> {
+            Ok(true)
+        }
+
+        fn busy(&self) -> Result<bool, ()> {
+            Ok(true)
+        }
+
+        fn halt(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    let mut sm = StateMachine::new(Context);
+    sm.process_event(Events::Start).unwrap();
+    sm.process_event(Events::Tick).unwrap();
+    StateMachine::new(Context)
+        .process_event(Events::Start)
+        .unwrap();
+    assert!(StateMachine::new(Context)
+        .process_event(Events::Stop)
+        .is_err());
+
+    let report = StateMachine::<Context>::coverage();
+    assert_eq!(report.covered().count(), 2);
+    let uncovered: Vec<_> = report.uncovered().map(|t| t.to_string()).collect();
+    assert_eq!(
+        uncovered,
+        [
+            "Idle + Start = Failed",
+            "Running + Stop [!busy] / halt = Idle"
+        ]
+    );
+    assert_eq!(report.percent(), 50.0);
+    assert_eq!(
+        report.to_string(),
+        "2 of 4 transitions covered (50.0%)\n  Idle + Start = Failed\n  Running + Stop [!busy] = Idle"
+    );
+    report.assert_min(50.0);
+    assert!(std::panic::catch_unwind(|| report.assert_min(75.0)).is_err());
+
+    StateMachine::<Context>::reset_coverage();
+    assert_eq!(StateMachine::<Context>::coverage().covered().count(), 0);
 }